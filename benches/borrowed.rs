@@ -0,0 +1,69 @@
+//! Compares parsing into the owned `api` types with the borrowed `borrowed` types.
+//!
+//! Run with `cargo bench --bench borrowed`.
+extern crate jsonapi;
+extern crate serde_json;
+
+use jsonapi::api::DocumentData;
+use jsonapi::borrowed::DocumentRef;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RESOURCES: usize = 2_000;
+const ITERATIONS: u32 = 50;
+
+fn document() -> String {
+    let resources: Vec<String> = (0..RESOURCES)
+        .map(|id| {
+            format!(
+                r#"{{ "type": "articles", "id": "{id}",
+                     "attributes": {{ "title": "Article {id}", "body": "{body}", "words": {id}, "tags": ["a", "b", "c"] }},
+                     "relationships": {{ "author": {{ "data": {{ "type": "people", "id": "{author}" }} }} }},
+                     "links": {{ "self": "https://example.com/articles/{id}" }} }}"#,
+                id = id,
+                author = id % 10,
+                body = "lorem ipsum ".repeat(20),
+            )
+        })
+        .collect();
+    format!(r#"{{ "data": [{}], "meta": {{ "total": {} }} }}"#, resources.join(","), RESOURCES)
+}
+
+fn time<F: FnMut()>(name: &str, mut f: F) {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed: Duration = start.elapsed() / ITERATIONS;
+    println!("{:<32} {:>10.3} ms/iter", name, elapsed.as_secs_f64() * 1000.0);
+}
+
+fn main() {
+    let input = document();
+    println!("document of {} resources, {} bytes", RESOURCES, input.len());
+
+    time("owned: parse", || {
+        black_box(serde_json::from_str::<DocumentData>(black_box(&input)).unwrap());
+    });
+    time("borrowed: parse", || {
+        black_box(DocumentRef::from_str(black_box(&input)).unwrap());
+    });
+
+    time("owned: parse + read title", || {
+        let doc: DocumentData = serde_json::from_str(black_box(&input)).unwrap();
+        for resource in doc.primary_resources() {
+            black_box(resource.attributes["title"].as_str());
+        }
+    });
+    time("borrowed: parse + read title", || {
+        let doc = DocumentRef::from_str(black_box(&input)).unwrap();
+        for resource in doc.primary_resources() {
+            black_box(resource.attribute::<&str>("title").unwrap());
+        }
+    });
+
+    time("borrowed: parse + to_document", || {
+        black_box(DocumentRef::from_str(black_box(&input)).unwrap().to_document().unwrap());
+    });
+}
@@ -1,9 +1,12 @@
 //! Defines custom types and structs primarily that composite the JSON:API
 //! document
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::errors::*;
 use std::str::FromStr;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_path_to_error::Segment;
 use std;
 
 /// Permitted JSON-API values (all JSON Values)
@@ -13,13 +16,14 @@ pub type JsonApiValue = serde_json::Value;
 pub type Resources = Vec<Resource>;
 /// Vector of `ResourceIdentifiers`
 pub type ResourceIdentifiers = Vec<ResourceIdentifier>;
-pub type Links = HashMap<String, JsonApiValue>;
+pub use crate::map::JsonApiMap;
+pub type Links = JsonApiMap<JsonApiValue>;
 /// Meta-data object, can contain any data
-pub type Meta = HashMap<String, JsonApiValue>;
+pub type Meta = JsonApiMap<JsonApiValue>;
 /// Resource Attributes, can be any JSON value
-pub type ResourceAttributes = HashMap<String, JsonApiValue>;
+pub type ResourceAttributes = JsonApiMap<JsonApiValue>;
 /// Map of relationships with other objects
-pub type Relationships = HashMap<String, Relationship>;
+pub type Relationships = JsonApiMap<Relationship>;
 /// Side-loaded Resources
 pub type Included = Vec<Resource>;
 /// Data-related errors
@@ -118,11 +122,105 @@ pub enum JsonApiDocument {
     Data(DocumentData),
 }
 
+/// A JSON:API document whose primary data is resource linkage rather than
+/// full resources. This is the body of requests to and responses from
+/// relationship endpoints, e.g. `/articles/1/relationships/tags`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RelationshipDocument {
+    pub data: IdentifierData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonApiInfo>,
+}
+
+/// The operation requested against a relationship endpoint, named after the
+/// HTTP method used
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum RelationshipOperation {
+    /// `PATCH` - replace the linkage entirely
+    Replace,
+    /// `POST` - add members to a to-many relationship
+    Add,
+    /// `DELETE` - remove members from a to-many relationship
+    Remove,
+}
+
+/// Resource identifiers to link and unlink in order to carry out a
+/// relationship operation
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LinkageChanges {
+    pub added: ResourceIdentifiers,
+    pub removed: ResourceIdentifiers,
+}
+
+/// Resource object as sent by a client in a `POST` request to create it. The
+/// `id` member is optional as the server will usually assign one. A missing
+/// `type` is parsed as an empty string and reported by
+/// [`validate`](#method.validate)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct NewResource {
+    #[serde(rename = "type", default)]
+    pub _type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<JsonApiId>,
+    #[serde(default)]
+    pub attributes: ResourceAttributes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Relationships>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// Resource object as sent by a client in a `PATCH` request to update it.
+/// Any `attributes` or `relationships` that are not present are left
+/// untouched by the server. A missing `type` or `id` is parsed as an empty
+/// string and reported by [`validate`](#method.validate)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ResourceUpdate {
+    #[serde(rename = "type", default)]
+    pub _type: String,
+    #[serde(default)]
+    pub id: JsonApiId,
+    #[serde(default)]
+    pub attributes: ResourceAttributes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Relationships>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// Request document used to create a resource
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct NewResourceDocument {
+    pub data: NewResource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonApiInfo>,
+}
+
+/// Request document used to update a resource
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ResourceUpdateDocument {
+    pub data: ResourceUpdate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonApiInfo>,
+}
+
 /// Error location
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct ErrorSource {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pointer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
 }
 
 /// Retpresentation of a JSON:API error (all fields are optional)
@@ -147,15 +245,27 @@ pub struct JsonApiError {
 }
 
 /// Optional `JsonApiDocument` payload identifying the JSON-API version the
-/// server implements
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// server implements, and the URIs of the extensions and profiles applied to
+/// the document
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct JsonApiInfo {
     pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ext: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Vec<String>>,
     pub meta: Option<Meta>,
 }
 
+/// Resources indexed by `type` and `id`, typically the `included` resources
+/// of a document, for constant time lookup of relationship linkage
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IncludedIndex {
+    resources: HashMap<(String, JsonApiId), Resource>,
+}
+
 /// Pagination links
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Pagination {
     pub first: Option<String>,
     pub prev: Option<String>,
@@ -164,21 +274,51 @@ pub struct Pagination {
 }
 
 
-#[derive(Debug)]
+/// A single change to a `Resource`. The `subject` is the name of the
+/// attribute, relationship, link or meta member that changed. `previous` is
+/// `null` for `Add` operations and `next` is `null` for `Remove` operations
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Patch {
     pub patch_type: PatchType,
+    pub operation: PatchOperation,
     pub subject: String,
     pub previous: JsonApiValue,
     pub next: JsonApiValue,
+    /// Position of the resource identifier in the linkage of a
+    /// `PatchType::RelationshipMember` patch: where an `Add` inserts it and
+    /// where a `Remove` expects to find it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
 }
 
-#[derive(Debug)]
+/// The set of changes between two versions of a `Resource`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PatchSet {
     pub resource_type: String,
     pub resource_id: String,
     pub patches: Vec<Patch>,
 }
 
+/// The changes between two collections of resources, matched by `type` and
+/// `id`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ResourcesDiff {
+    /// Resources only present in the new collection
+    pub created: Resources,
+    /// Resources only present in the old collection
+    pub deleted: Resources,
+    /// Changes to resources present in both collections
+    pub modified: Vec<PatchSet>,
+}
+
+/// The changes between the primary data and the included resources of two
+/// documents
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct DocumentDiff {
+    pub data: ResourcesDiff,
+    pub included: ResourcesDiff,
+}
+
 impl PatchSet {
     pub fn new_for(resource: &Resource) -> Self {
         PatchSet {
@@ -191,6 +331,38 @@ impl PatchSet {
     pub fn push(&mut self, patch: Patch) {
         self.patches.push(patch);
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    /// Return the `PatchSet` that undoes this one. Patches are reversed in
+    /// order, `Add` and `Remove` are swapped and so are `previous` and `next`
+    pub fn invert(&self) -> PatchSet {
+        PatchSet {
+            resource_type: self.resource_type.clone(),
+            resource_id: self.resource_id.clone(),
+            patches: self.patches.iter().rev().map(Patch::invert).collect(),
+        }
+    }
+}
+
+impl Patch {
+    /// Return the `Patch` that undoes this one
+    pub fn invert(&self) -> Patch {
+        Patch {
+            patch_type: self.patch_type,
+            operation: match self.operation {
+                PatchOperation::Add => PatchOperation::Remove,
+                PatchOperation::Remove => PatchOperation::Add,
+                PatchOperation::Replace => PatchOperation::Replace,
+            },
+            subject: self.subject.clone(),
+            previous: self.next.clone(),
+            next: self.previous.clone(),
+            index: self.index,
+        }
+    }
 }
 
 impl DocumentData {
@@ -203,6 +375,41 @@ impl DocumentData {
     fn has_data(&self) -> bool {
         self.data.is_some()
     }
+
+    /// The resources in the primary data as a slice, whether the document
+    /// holds a single resource or a collection
+    pub fn primary_resources(&self) -> &[Resource] {
+        match self.data {
+            Some(PrimaryData::Single(ref resource)) => std::slice::from_ref(resource.as_ref()),
+            Some(PrimaryData::Multiple(ref resources)) => resources,
+            Some(PrimaryData::None) | None => &[],
+        }
+    }
+
+    /// The pagination links of the document. Links can be given as a URL or
+    /// as a link object with an `href` member
+    pub fn pagination(&self) -> Pagination {
+        let link = |name: &str| {
+            self.links
+                .as_ref()
+                .and_then(|links| links.get(name))
+                .and_then(link_href)
+        };
+        Pagination {
+            first: link("first"),
+            prev: link("prev"),
+            next: link("next"),
+            last: link("last"),
+        }
+    }
+}
+
+fn link_href(link: &JsonApiValue) -> Option<String> {
+    match *link {
+        JsonApiValue::String(ref href) => Some(href.clone()),
+        JsonApiValue::Object(ref object) => object.get("href").and_then(JsonApiValue::as_str).map(String::from),
+        _ => None,
+    }
 }
 
 /// Top-level JSON-API Document
@@ -278,6 +485,142 @@ impl JsonApiDocument {
             }
         }
     }
+
+    /// Compare the primary data and the included resources of this document
+    /// with those of `other`. See [`ResourcesDiff::between`](struct.ResourcesDiff.html#method.between).
+    ///
+    /// Both documents must be data documents, comparing an error document
+    /// returns `DiffPatchError::NotADataDocument`.
+    pub fn diff(&self, other: &JsonApiDocument) -> std::result::Result<DocumentDiff, DiffPatchError> {
+        match (self, other) {
+            (JsonApiDocument::Data(previous), JsonApiDocument::Data(next)) => Ok(DocumentDiff {
+                data: ResourcesDiff::between(previous.primary_resources(), next.primary_resources())?,
+                included: ResourcesDiff::between(
+                    previous.included.as_deref().unwrap_or(&[]),
+                    next.included.as_deref().unwrap_or(&[]),
+                )?,
+            }),
+            _ => Err(DiffPatchError::NotADataDocument),
+        }
+    }
+}
+
+impl IncludedIndex {
+    /// Build an index of `resources`. If a resource appears more than once
+    /// the first occurrence is kept
+    pub fn new(resources: &[Resource]) -> Self {
+        resources.iter().cloned().collect()
+    }
+
+    /// Build an index of the `included` resources of a document
+    pub fn from_document(doc: &DocumentData) -> Self {
+        doc.included.as_deref().map(IncludedIndex::new).unwrap_or_default()
+    }
+
+    pub fn get(&self, identifier: &ResourceIdentifier) -> Option<&Resource> {
+        self.resources
+            .get(&(identifier._type.clone(), identifier.id.clone()))
+    }
+
+    /// Add a resource to the index, replacing and returning any resource with
+    /// the same `type` and `id`
+    pub fn insert(&mut self, resource: Resource) -> Option<Resource> {
+        self.resources
+            .insert((resource._type.clone(), resource.id.clone()), resource)
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
+impl FromIterator<Resource> for IncludedIndex {
+    fn from_iter<I: IntoIterator<Item = Resource>>(iter: I) -> Self {
+        let mut index = IncludedIndex::default();
+        for resource in iter {
+            index
+                .resources
+                .entry((resource._type.clone(), resource.id.clone()))
+                .or_insert(resource);
+        }
+        index
+    }
+}
+
+impl ResourcesDiff {
+    /// Match the resources in `previous` and `next` by `type` and `id` and
+    /// report which were created, deleted or modified. Created and modified
+    /// resources are listed in the order of `next`, deleted ones in the order
+    /// of `previous`. Unchanged resources are left out.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let previous: Resources = serde_json::from_str(r#"[
+    ///   { "type": "posts", "id": "1", "attributes": { "title": "One" } },
+    ///   { "type": "posts", "id": "2", "attributes": { "title": "Two" } }
+    /// ]"#).unwrap();
+    /// let next: Resources = serde_json::from_str(r#"[
+    ///   { "type": "posts", "id": "2", "attributes": { "title": "Deux" } },
+    ///   { "type": "posts", "id": "3", "attributes": { "title": "Three" } }
+    /// ]"#).unwrap();
+    ///
+    /// let diff = ResourcesDiff::between(&previous, &next).unwrap();
+    /// assert_eq!(diff.created[0].id, "3");
+    /// assert_eq!(diff.deleted[0].id, "1");
+    /// assert_eq!(diff.modified[0].resource_id, "2");
+    /// ```
+    pub fn between(
+        previous: &[Resource],
+        next: &[Resource],
+    ) -> std::result::Result<ResourcesDiff, DiffPatchError> {
+        let key = |r: &Resource| (r._type.clone(), r.id.clone());
+        let mut previous_by_key = HashMap::new();
+        for resource in previous {
+            previous_by_key.entry(key(resource)).or_insert(resource);
+        }
+        let next_keys: HashSet<_> = next.iter().map(key).collect();
+
+        // Only the first occurrence of a duplicated resource is considered
+        let mut seen = HashSet::new();
+        let mut diff = ResourcesDiff::default();
+        for resource in next {
+            if !seen.insert(key(resource)) {
+                continue;
+            }
+            match previous_by_key.get(&key(resource)) {
+                None => diff.created.push(resource.clone()),
+                Some(previous_resource) => {
+                    let patchset = previous_resource.diff(resource.clone())?;
+                    if !patchset.is_empty() {
+                        diff.modified.push(patchset);
+                    }
+                }
+            }
+        }
+        seen.clear();
+        for resource in previous {
+            if seen.insert(key(resource)) && !next_keys.contains(&key(resource)) {
+                diff.deleted.push(resource.clone());
+            }
+        }
+
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.deleted.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl DocumentDiff {
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.included.is_empty()
+    }
 }
 
 impl FromStr for JsonApiDocument {
@@ -299,8 +642,45 @@ impl FromStr for JsonApiDocument {
     /// let doc = JsonApiDocument::from_str(&serialized);
     /// assert_eq!(doc.is_ok(), true);
     /// ```
+    ///
+    /// Failures are reported as `Error::Parse` with a JSON Pointer to the
+    /// offending member, which converts into a `DocumentError`
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use jsonapi::errors::*;
+    /// use std::str::FromStr;
+    ///
+    /// let serialized = r#"{
+    ///   "data" : [
+    ///     { "id":"1", "type":"post", "attributes":{} },
+    ///     { "id":"2", "attributes":{} }
+    ///   ]
+    /// }"#;
+    /// match JsonApiDocument::from_str(&serialized) {
+    ///     Err(Error::Parse { pointer, .. }) => {
+    ///         assert_eq!(pointer, Some("/data/1".into()));
+    ///     }
+    ///     _ => assert!(false),
+    /// }
+    /// ```
     fn from_str(s: &str) -> Result<Self> {
-        serde_json::from_str(s).chain_err(|| "Error parsing Document")
+        let value = parse_value(s)?;
+        if value.get("errors").is_some() {
+            return deserialize_tracked(value, "").map(JsonApiDocument::Error);
+        }
+
+        let primary = value.get("data").cloned();
+        deserialize_tracked(value, "")
+            .map(JsonApiDocument::Data)
+            .map_err(|err| match (&err, primary) {
+                // PrimaryData is untagged, so the path ends at "/data";
+                // deserialize the resources directly to find the member
+                (Error::Parse { pointer: Some(pointer), .. }, Some(primary)) if pointer == "/data" => {
+                    locate_primary_data_error(primary).unwrap_or(err)
+                }
+                _ => err,
+            })
     }
 }
 
@@ -357,105 +737,845 @@ impl Resource {
         }
     }
 
+    /// Get an attribute deserialized into `T`
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let resource = Resource::from_str(r#"{
+    ///   "id": "1", "type": "post",
+    ///   "attributes": { "title": "Rails is Omakase", "likes": 250 }
+    /// }"#).unwrap();
+    ///
+    /// assert_eq!(resource.get_attribute_as::<u32>("likes"), Ok(250));
+    /// assert_eq!(
+    ///     resource.get_attribute_as::<u32>("title"),
+    ///     Err(JsonApiDataError::IncompatibleAttributeType)
+    /// );
+    /// assert_eq!(
+    ///     resource.get_attribute_as::<u32>("views"),
+    ///     Err(JsonApiDataError::AttributeNotFound)
+    /// );
+    /// ```
+    pub fn get_attribute_as<T: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> std::result::Result<T, JsonApiDataError> {
+        value_as(self.get_attribute(name))
+    }
+
+    /// Get a value nested inside the attributes using a JSON Pointer whose
+    /// first token is the attribute name, e.g. `/address/city`
+    pub fn get_attribute_pointer(&self, pointer: &str) -> Option<&JsonApiValue> {
+        let (name, rest) = split_attribute_pointer(pointer)?;
+        self.attributes.get(&name).and_then(|value| value.pointer(rest))
+    }
+
+    /// Get a value nested inside the attributes using a JSON Pointer,
+    /// deserialized into `T`
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let resource = Resource::from_str(r#"{
+    ///   "id": "1", "type": "people",
+    ///   "attributes": { "address": { "city": "Amsterdam", "lines": ["Dam 1"] } }
+    /// }"#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     resource.get_attribute_pointer_as::<String>("/address/lines/0"),
+    ///     Ok("Dam 1".to_string())
+    /// );
+    /// ```
+    pub fn get_attribute_pointer_as<T: DeserializeOwned>(
+        &self,
+        pointer: &str,
+    ) -> std::result::Result<T, JsonApiDataError> {
+        value_as(self.get_attribute_pointer(pointer))
+    }
+
+    /// Set an attribute, returning its previous value if there was one.
+    ///
+    /// The specification reserves `id` and `type`, and the names of the
+    /// resource's relationships share a namespace with its attributes; any of
+    /// these is rejected with `JsonApiDataError::ReservedAttributeName`, as
+    /// is a value containing an object with a `relationships` or `links`
+    /// member.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let mut resource = Resource {
+    ///     _type: "post".into(),
+    ///     id: "1".into(),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(resource.set_attribute("likes", 250), Ok(None));
+    /// assert_eq!(resource.get_attribute_as::<u32>("likes"), Ok(250));
+    /// assert_eq!(
+    ///     resource.set_attribute("type", "article"),
+    ///     Err(JsonApiDataError::ReservedAttributeName)
+    /// );
+    /// ```
+    pub fn set_attribute<T: Serialize>(
+        &mut self,
+        name: &str,
+        value: T,
+    ) -> std::result::Result<Option<JsonApiValue>, JsonApiDataError> {
+        let value = attribute_value(value)?;
+        if name == "id" || name == "type" || self.get_relationship(name).is_some() {
+            return Err(JsonApiDataError::ReservedAttributeName);
+        }
+        Ok(self.attributes.insert(name.to_string(), value))
+    }
+
+    /// Set a value nested inside the attributes using a JSON Pointer whose
+    /// first token is the attribute name, returning the previous value if
+    /// there was one. The attribute and every value along the pointer except
+    /// the last must already exist. As with JSON Patch `add`, a last token of
+    /// `-` appends to an array.
+    pub fn set_attribute_pointer<T: Serialize>(
+        &mut self,
+        pointer: &str,
+        value: T,
+    ) -> std::result::Result<Option<JsonApiValue>, JsonApiDataError> {
+        let value = attribute_value(value)?;
+        let (name, rest) =
+            split_attribute_pointer(pointer).ok_or(JsonApiDataError::AttributeNotFound)?;
+        if rest.is_empty() {
+            return self.set_attribute(&name, value);
+        }
+
+        let (parent, last) = match rest.rfind('/') {
+            Some(idx) => (&rest[..idx], unescape_pointer_token(&rest[idx + 1..])),
+            None => return Err(JsonApiDataError::AttributeNotFound),
+        };
+
+        let target = self
+            .attributes
+            .get_mut(&name)
+            .and_then(|attr| attr.pointer_mut(parent))
+            .ok_or(JsonApiDataError::AttributeNotFound)?;
+        match *target {
+            JsonApiValue::Object(ref mut map) => {
+                if last == "relationships" || last == "links" {
+                    return Err(JsonApiDataError::ReservedAttributeName);
+                }
+                Ok(map.insert(last, value))
+            }
+            JsonApiValue::Array(ref mut items) => {
+                if last == "-" {
+                    items.push(value);
+                    return Ok(None);
+                }
+                match last.parse::<usize>() {
+                    Ok(idx) if idx < items.len() => {
+                        Ok(Some(std::mem::replace(&mut items[idx], value)))
+                    }
+                    Ok(idx) if idx == items.len() => {
+                        items.push(value);
+                        Ok(None)
+                    }
+                    _ => Err(JsonApiDataError::AttributeNotFound),
+                }
+            }
+            _ => Err(JsonApiDataError::IncompatibleAttributeType),
+        }
+    }
+
+    /// Describe the changes required to turn this resource into `other` as a
+    /// `PatchSet`. Added, removed and changed attributes, relationships,
+    /// links and meta are all reported. Patches are ordered by kind and then
+    /// by subject.
+    ///
+    /// A to-many relationship whose links are unchanged is described by
+    /// `PatchType::RelationshipMember` patches, one for each resource
+    /// identifier removed or added, with its position in the linkage as
+    /// `index`. Any other change to a relationship, including a change to the
+    /// order of its members, replaces the `Relationship` object as a whole.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let before = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1",
+    ///   "attributes": { "title": "Rails is Omakase", "likes": 250 }
+    /// }"#).unwrap();
+    ///
+    /// let after = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1",
+    ///   "attributes": { "title": "Rails is Omakase", "score": 5 }
+    /// }"#).unwrap();
+    ///
+    /// let patchset = before.diff(after).unwrap();
+    /// assert_eq!(patchset.patches.len(), 2);
+    /// assert_eq!(patchset.patches[0].operation, PatchOperation::Remove);
+    /// assert_eq!(patchset.patches[0].subject, "likes");
+    /// assert_eq!(patchset.patches[1].operation, PatchOperation::Add);
+    /// assert_eq!(patchset.patches[1].subject, "score");
+    /// ```
     pub fn diff(&self, other: Resource) -> std::result::Result<PatchSet, DiffPatchError> {
         if self._type != other._type {
-            Err(DiffPatchError::IncompatibleTypes(
+            return Err(DiffPatchError::IncompatibleTypes(
                 self._type.clone(),
                 other._type.clone(),
-            ))
-        } else {
+            ));
+        }
 
-            let mut self_keys: Vec<String> =
-                self.attributes.iter().map(|(key, _)| key.clone()).collect();
+        let mut patchset = PatchSet::new_for(self);
 
-            self_keys.sort();
+        diff_maps(
+            &mut patchset,
+            PatchType::Attribute,
+            &self.attributes,
+            &other.attributes,
+        );
 
-            let mut other_keys: Vec<String> = other
-                .attributes
-                .iter()
-                .map(|(key, _)| key.clone())
-                .collect();
+        let empty_relationships = Relationships::new();
+        let self_relationships = self.relationships.as_ref().unwrap_or(&empty_relationships);
+        let other_relationships = other.relationships.as_ref().unwrap_or(&empty_relationships);
+        for name in sorted_keys(self_relationships, other_relationships) {
+            match (self_relationships.get(name), other_relationships.get(name)) {
+                (Some(previous), Some(next)) => {
+                    diff_relationship(&mut patchset, name, previous, next)
+                }
+                (None, Some(next)) => patchset.push(Patch {
+                    patch_type: PatchType::Relationship,
+                    operation: PatchOperation::Add,
+                    subject: name.clone(),
+                    previous: JsonApiValue::Null,
+                    next: relationship_to_value(next),
+                    index: None,
+                }),
+                (Some(previous), None) => patchset.push(Patch {
+                    patch_type: PatchType::Relationship,
+                    operation: PatchOperation::Remove,
+                    subject: name.clone(),
+                    previous: relationship_to_value(previous),
+                    next: JsonApiValue::Null,
+                    index: None,
+                }),
+                (None, None) => {}
+            }
+        }
 
-            other_keys.sort();
+        let empty = JsonApiMap::new();
+        diff_maps(
+            &mut patchset,
+            PatchType::Link,
+            self.links.as_ref().unwrap_or(&empty),
+            other.links.as_ref().unwrap_or(&empty),
+        );
+        diff_maps(
+            &mut patchset,
+            PatchType::Meta,
+            self.meta.as_ref().unwrap_or(&empty),
+            other.meta.as_ref().unwrap_or(&empty),
+        );
 
-            let matching = self_keys
-                .iter()
-                .zip(other_keys.iter())
-                .filter(|&(a, b)| a == b)
-                .count();
+        Ok(patchset)
+    }
 
-            if matching != self_keys.len() {
-                Err(DiffPatchError::DifferentAttributeKeys)
+    /// Like [`diff`](#method.diff), but attributes whose values are both
+    /// objects or both arrays are compared recursively. Each nested change is
+    /// a separate patch whose `subject` is a JSON Pointer to the changed
+    /// value, e.g. `/address/city` or `/tags/1`. Member names can't contain
+    /// `/`, so a pointer never clashes with the name of an attribute.
+    ///
+    /// Arrays are compared by position: elements present in both are diffed,
+    /// extra elements in `other` are added at their index and surplus ones
+    /// are removed starting from the end.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let before = Resource::from_str(r#"{
+    ///   "type": "people", "id": "1",
+    ///   "attributes": { "address": { "city": "Amsterdam", "zip": "1011" } }
+    /// }"#).unwrap();
+    /// let after = Resource::from_str(r#"{
+    ///   "type": "people", "id": "1",
+    ///   "attributes": { "address": { "city": "Utrecht", "zip": "1011" } }
+    /// }"#).unwrap();
+    ///
+    /// let patchset = before.diff_deep(after.clone()).unwrap();
+    /// assert_eq!(patchset.patches.len(), 1);
+    /// assert_eq!(patchset.patches[0].subject, "/address/city");
+    /// assert_eq!(before.patch(&patchset).unwrap(), after);
+    /// ```
+    pub fn diff_deep(&self, other: Resource) -> std::result::Result<PatchSet, DiffPatchError> {
+        let mut patchset = self.diff(other)?;
+        let patches = std::mem::take(&mut patchset.patches);
+        for patch in patches {
+            let nested = matches!(
+                (&patch.previous, &patch.next),
+                (JsonApiValue::Object(_), JsonApiValue::Object(_)) | (JsonApiValue::Array(_), JsonApiValue::Array(_))
+            );
+            if patch.patch_type == PatchType::Attribute && patch.operation == PatchOperation::Replace && nested {
+                let path = format!("/{}", escape_pointer_token(&patch.subject));
+                diff_values(&mut patchset, &path, &patch.previous, &patch.next);
             } else {
-                let mut patchset = PatchSet::new_for(self);
-
-                for (attr, self_value) in &self.attributes {
-                    match other.attributes.get(attr) {
-                        None => {
-                            error!(
-                                "Resource::diff unable to find attribute {:?} in {:?}",
-                                attr,
-                                other
-                            );
-                        }
-                        Some(other_value) => {
-                            if self_value != other_value {
-                                patchset.push(Patch {
-                                    patch_type: PatchType::Attribute,
-                                    subject: attr.clone(),
-                                    previous: self_value.clone(),
-                                    next: other_value.clone(),
-                                });
-                            }
-                        }
-                    }
-
-                }
-
-                Ok(patchset)
+                patchset.push(patch);
             }
         }
+        Ok(patchset)
     }
 
-    pub fn patch(&mut self, patchset: PatchSet) -> Result<Resource> {
+    /// Return a copy of this resource with every patch in `patchset` applied.
+    /// See [`apply`](#method.apply) for the checks that are performed.
+    pub fn patch(&self, patchset: &PatchSet) -> std::result::Result<Resource, DiffPatchError> {
         let mut res = self.clone();
-        for patch in &patchset.patches {
-            res.attributes.insert(
-                patch.subject.clone(),
-                patch.next.clone(),
-            );
-        }
+        res.apply(patchset)?;
         Ok(res)
     }
-}
 
-impl FromStr for Resource {
-    type Err = Error;
-
-    /// Instantiate from string
+    /// Apply every patch in `patchset` to this resource in place. Attribute
+    /// subjects may be JSON Pointers as produced by
+    /// [`diff_deep`](#method.diff_deep).
+    ///
+    /// This is a three-way apply: the `previous` value of each patch must
+    /// match the current state of the resource. An `Add` fails with
+    /// `DiffPatchError::IncorrectPropertyValue` if the subject already exists,
+    /// a `Remove` or `Replace` fails with `DiffPatchError::NonExistentProperty`
+    /// if it doesn't and with `DiffPatchError::IncorrectPropertyValue` if its
+    /// current value differs from `previous`. If any patch conflicts the
+    /// resource is left unchanged.
     ///
     /// ```
-    /// use jsonapi::api::Resource;
+    /// use jsonapi::api::*;
     /// use std::str::FromStr;
     ///
-    /// let serialized = r#"{
-    ///   "id":"1",
-    ///   "type":"post",
-    ///   "attributes":{
-    ///     "title": "Rails is Omakase",
-    ///     "likes": 250
-    ///   },
-    ///   "relationships":{},
-    ///   "links" :{}
-    /// }"#;
+    /// let before = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1", "attributes": { "likes": 250 }
+    /// }"#).unwrap();
+    /// let after = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1", "attributes": { "likes": 251 }
+    /// }"#).unwrap();
     ///
-    /// let data = Resource::from_str(&serialized);
-    /// assert_eq!(data.is_ok(), true);
+    /// let patchset = before.diff(after.clone()).unwrap();
+    ///
+    /// let mut resource = before.clone();
+    /// resource.apply(&patchset).unwrap();
+    /// assert_eq!(resource, after);
+    ///
+    /// // The same patchset no longer applies once it has been applied
+    /// assert_eq!(
+    ///     resource.apply(&patchset),
+    ///     Err(DiffPatchError::IncorrectPropertyValue("likes".into()))
+    /// );
+    ///
+    /// // But its inverse does
+    /// resource.apply(&patchset.invert()).unwrap();
+    /// assert_eq!(resource, before);
     /// ```
-    fn from_str(s: &str) -> Result<Self> {
-        serde_json::from_str(s).chain_err(|| "Error parsing resource")
-    }
-}
+    pub fn apply(&mut self, patchset: &PatchSet) -> std::result::Result<(), DiffPatchError> {
+        if self._type != patchset.resource_type {
+            return Err(DiffPatchError::IncompatibleTypes(
+                self._type.clone(),
+                patchset.resource_type.clone(),
+            ));
+        }
+        if self.id != patchset.resource_id {
+            return Err(DiffPatchError::IncorrectPropertyValue("id".into()));
+        }
+
+        let mut res = self.clone();
+        for patch in &patchset.patches {
+            match patch.patch_type {
+                PatchType::Attribute => apply_to_attributes(&mut res.attributes, patch)?,
+                PatchType::Link => apply_to_map(res.links.get_or_insert_with(Links::new), patch)?,
+                PatchType::Meta => apply_to_map(res.meta.get_or_insert_with(Meta::new), patch)?,
+                PatchType::Relationship => apply_to_relationships(
+                    res.relationships.get_or_insert_with(Relationships::new),
+                    patch,
+                )?,
+                PatchType::RelationshipMember => apply_to_relationship_members(
+                    res.relationships.get_or_insert_with(Relationships::new),
+                    patch,
+                )?,
+            }
+        }
+
+        // `diff` treats an absent map and an empty one alike, so drop any map
+        // the patches left empty unless it was empty to begin with
+        clear_if_emptied(&self.relationships, &mut res.relationships);
+        clear_if_emptied(&self.links, &mut res.links);
+        clear_if_emptied(&self.meta, &mut res.meta);
+
+        *self = res;
+        Ok(())
+    }
+}
+
+fn value_as<T: DeserializeOwned>(
+    value: Option<&JsonApiValue>,
+) -> std::result::Result<T, JsonApiDataError> {
+    match value {
+        None => Err(JsonApiDataError::AttributeNotFound),
+        Some(value) => T::deserialize(value).map_err(|_| JsonApiDataError::IncompatibleAttributeType),
+    }
+}
+
+/// Serialize a value for use as an attribute, rejecting objects with members
+/// the specification reserves
+fn attribute_value<T: Serialize>(value: T) -> std::result::Result<JsonApiValue, JsonApiDataError> {
+    fn contains_reserved_member(value: &JsonApiValue) -> bool {
+        match *value {
+            JsonApiValue::Object(ref map) => {
+                map.contains_key("relationships")
+                    || map.contains_key("links")
+                    || map.values().any(contains_reserved_member)
+            }
+            JsonApiValue::Array(ref items) => items.iter().any(contains_reserved_member),
+            _ => false,
+        }
+    }
+
+    let value = serde_json::to_value(value).map_err(|_| JsonApiDataError::IncompatibleAttributeType)?;
+    if contains_reserved_member(&value) {
+        Err(JsonApiDataError::ReservedAttributeName)
+    } else {
+        Ok(value)
+    }
+}
+
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+pub(crate) fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Split a JSON Pointer into the attribute named by its first token and the
+/// pointer into that attribute's value
+pub(crate) fn split_attribute_pointer(pointer: &str) -> Option<(String, &str)> {
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    let rest = &pointer[1..];
+    match rest.find('/') {
+        Some(idx) => Some((unescape_pointer_token(&rest[..idx]), &rest[idx..])),
+        None => Some((unescape_pointer_token(rest), "")),
+    }
+}
+
+fn sorted_keys<'a, V>(a: &'a JsonApiMap<V>, b: &'a JsonApiMap<V>) -> Vec<&'a String> {
+    let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn diff_maps(
+    patchset: &mut PatchSet,
+    patch_type: PatchType,
+    previous: &JsonApiMap<JsonApiValue>,
+    next: &JsonApiMap<JsonApiValue>,
+) {
+    for key in sorted_keys(previous, next) {
+        match (previous.get(key), next.get(key)) {
+            (Some(previous_value), Some(next_value)) => {
+                if previous_value != next_value {
+                    patchset.push(Patch {
+                        patch_type,
+                        operation: PatchOperation::Replace,
+                        subject: key.clone(),
+                        previous: previous_value.clone(),
+                        next: next_value.clone(),
+                        index: None,
+                    });
+                }
+            }
+            (None, Some(next_value)) => patchset.push(Patch {
+                patch_type,
+                operation: PatchOperation::Add,
+                subject: key.clone(),
+                previous: JsonApiValue::Null,
+                next: next_value.clone(),
+                index: None,
+            }),
+            (Some(previous_value), None) => patchset.push(Patch {
+                patch_type,
+                operation: PatchOperation::Remove,
+                subject: key.clone(),
+                previous: previous_value.clone(),
+                next: JsonApiValue::Null,
+                index: None,
+            }),
+            (None, None) => {}
+        }
+    }
+}
+
+fn diff_values(patchset: &mut PatchSet, path: &str, previous: &JsonApiValue, next: &JsonApiValue) {
+    let nested_patch = |operation, key: &str, previous: &JsonApiValue, next: &JsonApiValue| Patch {
+        patch_type: PatchType::Attribute,
+        operation,
+        subject: format!("{}/{}", path, escape_pointer_token(key)),
+        previous: previous.clone(),
+        next: next.clone(),
+        index: None,
+    };
+
+    match (previous, next) {
+        (JsonApiValue::Object(previous_map), JsonApiValue::Object(next_map)) => {
+            let mut keys: Vec<&String> = previous_map.keys().chain(next_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                match (previous_map.get(key), next_map.get(key)) {
+                    (Some(previous_value), Some(next_value)) => diff_values(
+                        patchset,
+                        &format!("{}/{}", path, escape_pointer_token(key)),
+                        previous_value,
+                        next_value,
+                    ),
+                    (None, Some(next_value)) => patchset.push(nested_patch(
+                        PatchOperation::Add,
+                        key,
+                        &JsonApiValue::Null,
+                        next_value,
+                    )),
+                    (Some(previous_value), None) => patchset.push(nested_patch(
+                        PatchOperation::Remove,
+                        key,
+                        previous_value,
+                        &JsonApiValue::Null,
+                    )),
+                    (None, None) => {}
+                }
+            }
+        }
+        (JsonApiValue::Array(previous_items), JsonApiValue::Array(next_items)) => {
+            for (idx, (previous_value, next_value)) in
+                previous_items.iter().zip(next_items.iter()).enumerate()
+            {
+                diff_values(patchset, &format!("{}/{}", path, idx), previous_value, next_value);
+            }
+            for (idx, next_value) in next_items.iter().enumerate().skip(previous_items.len()) {
+                patchset.push(nested_patch(
+                    PatchOperation::Add,
+                    &idx.to_string(),
+                    &JsonApiValue::Null,
+                    next_value,
+                ));
+            }
+            for (idx, previous_value) in previous_items.iter().enumerate().skip(next_items.len()).rev() {
+                patchset.push(nested_patch(
+                    PatchOperation::Remove,
+                    &idx.to_string(),
+                    previous_value,
+                    &JsonApiValue::Null,
+                ));
+            }
+        }
+        _ => {
+            if previous != next {
+                patchset.push(Patch {
+                    patch_type: PatchType::Attribute,
+                    operation: PatchOperation::Replace,
+                    subject: path.to_string(),
+                    previous: previous.clone(),
+                    next: next.clone(),
+                    index: None,
+                });
+            }
+        }
+    }
+}
+
+fn diff_relationship(patchset: &mut PatchSet, name: &str, previous: &Relationship, next: &Relationship) {
+    if previous == next {
+        return;
+    }
+
+    if let (Some(IdentifierData::Multiple(previous_members)), Some(IdentifierData::Multiple(next_members))) =
+        (previous.data.as_ref(), next.data.as_ref())
+    {
+        // Members present in both must keep their order, otherwise adding
+        // and removing members can't describe the change
+        let in_order = previous_members
+            .iter()
+            .filter(|member| next_members.contains(member))
+            .eq(next_members.iter().filter(|member| previous_members.contains(member)));
+        if previous.links == next.links && in_order {
+            // Removing from the end first and then adding from the start
+            // keeps every index valid when the patches are applied in order
+            for (idx, member) in previous_members.iter().enumerate().rev() {
+                if !next_members.contains(member) {
+                    patchset.push(Patch {
+                        patch_type: PatchType::RelationshipMember,
+                        operation: PatchOperation::Remove,
+                        subject: name.to_string(),
+                        previous: identifier_to_value(member),
+                        next: JsonApiValue::Null,
+                        index: Some(idx),
+                    });
+                }
+            }
+            for (idx, member) in next_members.iter().enumerate() {
+                if !previous_members.contains(member) {
+                    patchset.push(Patch {
+                        patch_type: PatchType::RelationshipMember,
+                        operation: PatchOperation::Add,
+                        subject: name.to_string(),
+                        previous: JsonApiValue::Null,
+                        next: identifier_to_value(member),
+                        index: Some(idx),
+                    });
+                }
+            }
+            return;
+        }
+    }
+
+    patchset.push(Patch {
+        patch_type: PatchType::Relationship,
+        operation: PatchOperation::Replace,
+        subject: name.to_string(),
+        previous: relationship_to_value(previous),
+        next: relationship_to_value(next),
+        index: None,
+    });
+}
+
+fn apply_to_map(
+    map: &mut JsonApiMap<JsonApiValue>,
+    patch: &Patch,
+) -> std::result::Result<(), DiffPatchError> {
+    check_previous(map.get(&patch.subject), patch, |value| value == &patch.previous)?;
+    match patch.operation {
+        PatchOperation::Remove => {
+            map.remove(&patch.subject);
+        }
+        PatchOperation::Add | PatchOperation::Replace => {
+            map.insert(patch.subject.clone(), patch.next.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Apply an attribute patch whose subject may be a JSON Pointer into nested
+/// objects and arrays
+fn apply_to_attributes(
+    attributes: &mut ResourceAttributes,
+    patch: &Patch,
+) -> std::result::Result<(), DiffPatchError> {
+    let (name, rest) = match split_attribute_pointer(&patch.subject) {
+        Some(split) => split,
+        None => return apply_to_map(attributes, patch),
+    };
+
+    let mut tokens: Vec<String> = rest.split('/').skip(1).map(unescape_pointer_token).collect();
+    let last = match tokens.pop() {
+        Some(last) => last,
+        None => {
+            check_previous(attributes.get(&name), patch, |value| value == &patch.previous)?;
+            match patch.operation {
+                PatchOperation::Remove => {
+                    attributes.remove(&name);
+                }
+                PatchOperation::Add | PatchOperation::Replace => {
+                    attributes.insert(name, patch.next.clone());
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let non_existent = || DiffPatchError::NonExistentProperty(patch.subject.clone());
+    let mut target = attributes.get_mut(&name).ok_or_else(non_existent)?;
+    for token in &tokens {
+        target = match *target {
+            JsonApiValue::Object(ref mut map) => map.get_mut(token),
+            JsonApiValue::Array(ref mut items) => {
+                token.parse::<usize>().ok().and_then(move |idx| items.get_mut(idx))
+            }
+            _ => None,
+        }
+        .ok_or_else(non_existent)?;
+    }
+
+    match *target {
+        JsonApiValue::Object(ref mut map) => {
+            check_previous(map.get(&last), patch, |value| value == &patch.previous)?;
+            match patch.operation {
+                PatchOperation::Remove => {
+                    map.remove(&last);
+                }
+                PatchOperation::Add | PatchOperation::Replace => {
+                    map.insert(last, patch.next.clone());
+                }
+            }
+        }
+        JsonApiValue::Array(ref mut items) => {
+            let idx = last.parse::<usize>().map_err(|_| non_existent())?;
+            match patch.operation {
+                PatchOperation::Add => {
+                    if idx > items.len() {
+                        return Err(non_existent());
+                    }
+                    items.insert(idx, patch.next.clone());
+                }
+                PatchOperation::Remove | PatchOperation::Replace => {
+                    check_previous(items.get(idx), patch, |value| value == &patch.previous)?;
+                    if patch.operation == PatchOperation::Remove {
+                        items.remove(idx);
+                    } else {
+                        items[idx] = patch.next.clone();
+                    }
+                }
+            }
+        }
+        _ => return Err(non_existent()),
+    }
+    Ok(())
+}
+
+fn apply_to_relationships(
+    relationships: &mut Relationships,
+    patch: &Patch,
+) -> std::result::Result<(), DiffPatchError> {
+    check_previous(relationships.get(&patch.subject), patch, |relationship| {
+        relationship_to_value(relationship) == patch.previous
+    })?;
+    match patch.operation {
+        PatchOperation::Remove => {
+            relationships.remove(&patch.subject);
+        }
+        PatchOperation::Add | PatchOperation::Replace => {
+            let relationship = serde_json::from_value(patch.next.clone())
+                .map_err(|_| DiffPatchError::IncorrectPropertyValue(patch.subject.clone()))?;
+            relationships.insert(patch.subject.clone(), relationship);
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_relationship_members(
+    relationships: &mut Relationships,
+    patch: &Patch,
+) -> std::result::Result<(), DiffPatchError> {
+    let members = match relationships.get_mut(&patch.subject) {
+        Some(&mut Relationship {
+            data: Some(IdentifierData::Multiple(ref mut members)),
+            ..
+        }) => members,
+        _ => return Err(DiffPatchError::NonExistentProperty(patch.subject.clone())),
+    };
+
+    let value = match patch.operation {
+        PatchOperation::Remove => &patch.previous,
+        PatchOperation::Add | PatchOperation::Replace => &patch.next,
+    };
+    let identifier: ResourceIdentifier = serde_json::from_value(value.clone())
+        .map_err(|_| DiffPatchError::IncorrectPropertyValue(patch.subject.clone()))?;
+
+    match patch.operation {
+        PatchOperation::Remove => {
+            let idx = members
+                .iter()
+                .position(|x| *x == identifier)
+                .ok_or_else(|| DiffPatchError::NonExistentProperty(patch.subject.clone()))?;
+            if patch.index.is_some_and(|index| index != idx) {
+                return Err(DiffPatchError::IncorrectPropertyValue(patch.subject.clone()));
+            }
+            members.remove(idx);
+        }
+        PatchOperation::Add | PatchOperation::Replace => {
+            if members.contains(&identifier) {
+                return Err(DiffPatchError::IncorrectPropertyValue(patch.subject.clone()));
+            }
+            match patch.index {
+                Some(idx) if idx > members.len() => {
+                    return Err(DiffPatchError::NonExistentProperty(patch.subject.clone()))
+                }
+                Some(idx) => members.insert(idx, identifier),
+                None => members.push(identifier),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn clear_if_emptied<V>(
+    original: &Option<JsonApiMap<V>>,
+    patched: &mut Option<JsonApiMap<V>>,
+) {
+    if patched.as_ref().is_some_and(JsonApiMap::is_empty)
+        && !original.as_ref().is_some_and(JsonApiMap::is_empty)
+    {
+        *patched = None;
+    }
+}
+
+/// Verify that the `current` state of a patch subject is what the patch
+/// expects to find before it is applied
+fn check_previous<T, F>(
+    current: Option<&T>,
+    patch: &Patch,
+    matches_previous: F,
+) -> std::result::Result<(), DiffPatchError>
+where
+    F: Fn(&T) -> bool,
+{
+    match (patch.operation, current) {
+        (PatchOperation::Add, None) => Ok(()),
+        (PatchOperation::Add, Some(_)) => {
+            Err(DiffPatchError::IncorrectPropertyValue(patch.subject.clone()))
+        }
+        (_, None) => Err(DiffPatchError::NonExistentProperty(patch.subject.clone())),
+        (_, Some(value)) => {
+            if matches_previous(value) {
+                Ok(())
+            } else {
+                Err(DiffPatchError::IncorrectPropertyValue(patch.subject.clone()))
+            }
+        }
+    }
+}
+
+fn relationship_to_value(relationship: &Relationship) -> JsonApiValue {
+    serde_json::to_value(relationship).expect("Casting Relationship to value")
+}
+
+fn identifier_to_value(identifier: &ResourceIdentifier) -> JsonApiValue {
+    serde_json::to_value(identifier).expect("Casting ResourceIdentifier to value")
+}
+
+impl FromStr for Resource {
+    type Err = Error;
+
+    /// Instantiate from string
+    ///
+    /// ```
+    /// use jsonapi::api::Resource;
+    /// use std::str::FromStr;
+    ///
+    /// let serialized = r#"{
+    ///   "id":"1",
+    ///   "type":"post",
+    ///   "attributes":{
+    ///     "title": "Rails is Omakase",
+    ///     "likes": 250
+    ///   },
+    ///   "relationships":{},
+    ///   "links" :{}
+    /// }"#;
+    ///
+    /// let data = Resource::from_str(&serialized);
+    /// assert_eq!(data.is_ok(), true);
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        parse_tracked(s)
+    }
+}
 
 
 impl Relationship {
@@ -468,7 +1588,7 @@ impl Relationship {
         }
     }
 
-    pub fn as_ids(&self) -> std::result::Result<Option<JsonApiIds>, RelationshipAssumptionError> {
+    pub fn as_ids(&self) -> std::result::Result<Option<JsonApiIds<'_>>, RelationshipAssumptionError> {
         match self.data {
             Some(IdentifierData::None) => Ok(None),
             Some(IdentifierData::Single(_)) => Err(RelationshipAssumptionError::RelationshipIsNotAList),
@@ -478,6 +1598,541 @@ impl Relationship {
     }
 }
 
+impl RelationshipDocument {
+    pub fn new(data: IdentifierData) -> Self {
+        RelationshipDocument {
+            data,
+            links: None,
+            meta: None,
+            jsonapi: None,
+        }
+    }
+
+    /// Build a relationship endpoint response from a `Relationship`. Returns
+    /// `None` if the relationship carries no linkage
+    pub fn from_relationship(relationship: &Relationship) -> Option<Self> {
+        relationship.data.as_ref().map(|data| RelationshipDocument {
+            data: data.clone(),
+            links: relationship.links.clone(),
+            meta: None,
+            jsonapi: None,
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_none()
+    }
+
+    /// This function returns a `Vec` with identified linkage violations
+    /// enumerated in `LinkageValidationError`
+    pub fn validate(&self) -> Option<Vec<LinkageValidationError>> {
+        let mut errors = Vec::<LinkageValidationError>::new();
+        let identifiers = linkage_identifiers(&self.data);
+
+        if identifiers.iter().any(|x| x._type.is_empty()) {
+            errors.push(LinkageValidationError::MissingType);
+        }
+        if identifiers.iter().any(|x| x.id.is_empty()) {
+            errors.push(LinkageValidationError::MissingId);
+        }
+        for (idx, identifier) in identifiers.iter().enumerate() {
+            if identifiers[..idx].contains(identifier) {
+                errors.push(LinkageValidationError::DuplicateIdentifier);
+                break;
+            }
+        }
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+
+    /// Compute the identifiers that must be linked and unlinked to carry out
+    /// `operation` with this document against the `current` state of the
+    /// relationship. `current` may be `None` if the relationship is not yet
+    /// known to the server, in which case it is treated as empty.
+    ///
+    /// `Add` and `Remove` are only permitted for to-many relationships, and
+    /// `Replace` must not change the cardinality of an existing relationship.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let current = Relationship {
+    ///     data: Some(IdentifierData::Multiple(vec![
+    ///         ResourceIdentifier { _type: "tags".into(), id: "2".into() },
+    ///     ])),
+    ///     links: None,
+    /// };
+    ///
+    /// let doc = RelationshipDocument::from_str(r#"{
+    ///   "data": [
+    ///     { "type": "tags", "id": "2" },
+    ///     { "type": "tags", "id": "3" }
+    ///   ]
+    /// }"#).unwrap();
+    ///
+    /// let changes = doc.changes(RelationshipOperation::Add, Some(&current)).unwrap();
+    /// assert_eq!(changes.added.len(), 1);
+    /// assert_eq!(changes.added[0].id, "3");
+    /// assert!(changes.removed.is_empty());
+    /// ```
+    pub fn changes(
+        &self,
+        operation: RelationshipOperation,
+        current: Option<&Relationship>,
+    ) -> std::result::Result<LinkageChanges, RelationshipAssumptionError> {
+        let current_data = current.and_then(|rel| rel.data.as_ref());
+
+        match operation {
+            RelationshipOperation::Replace => match (current_data, &self.data) {
+                (Some(&IdentifierData::Single(_)), &IdentifierData::Multiple(_)) => {
+                    return Err(RelationshipAssumptionError::RelationshipIsAList)
+                }
+                (Some(&IdentifierData::Multiple(_)), &IdentifierData::Single(_))
+                | (Some(&IdentifierData::Multiple(_)), &IdentifierData::None) => {
+                    return Err(RelationshipAssumptionError::RelationshipIsNotAList)
+                }
+                _ => {}
+            },
+            RelationshipOperation::Add | RelationshipOperation::Remove => {
+                if let Some(&IdentifierData::Single(_)) = current_data {
+                    return Err(RelationshipAssumptionError::RelationshipIsNotAList);
+                }
+                match self.data {
+                    IdentifierData::Multiple(_) => {}
+                    _ => return Err(RelationshipAssumptionError::RelationshipIsNotAList),
+                }
+            }
+        }
+
+        let existing = match current_data {
+            Some(data) => linkage_identifiers(data),
+            None => Vec::new(),
+        };
+        let requested = linkage_identifiers(&self.data);
+
+        let mut changes = LinkageChanges::default();
+        match operation {
+            RelationshipOperation::Replace | RelationshipOperation::Add => {
+                for &identifier in &requested {
+                    if !existing.contains(&identifier) && !changes.added.contains(identifier) {
+                        changes.added.push(identifier.clone());
+                    }
+                }
+            }
+            RelationshipOperation::Remove => {}
+        }
+        match operation {
+            RelationshipOperation::Replace => {
+                for &identifier in &existing {
+                    if !requested.contains(&identifier) {
+                        changes.removed.push(identifier.clone());
+                    }
+                }
+            }
+            RelationshipOperation::Remove => {
+                for &identifier in &requested {
+                    if existing.contains(&identifier) && !changes.removed.contains(identifier) {
+                        changes.removed.push(identifier.clone());
+                    }
+                }
+            }
+            RelationshipOperation::Add => {}
+        }
+
+        Ok(changes)
+    }
+}
+
+impl FromStr for RelationshipDocument {
+    type Err = Error;
+
+    /// Instantiate from string
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let serialized = r#"{
+    ///   "data": { "type": "people", "id": "12" }
+    /// }"#;
+    /// let doc = RelationshipDocument::from_str(&serialized);
+    /// assert_eq!(doc.is_ok(), true);
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        parse_tracked(s)
+    }
+}
+
+fn parse_value(s: &str) -> Result<JsonApiValue> {
+    serde_json::from_str(s).map_err(|err| Error::Parse {
+        pointer: None,
+        detail: err.to_string(),
+    })
+}
+
+/// Parse `s`, reporting failures with a JSON Pointer to the offending member
+fn parse_tracked<T: DeserializeOwned>(s: &str) -> Result<T> {
+    deserialize_tracked(parse_value(s)?, "")
+}
+
+/// Deserialize `value`, reporting failures with a JSON Pointer to the
+/// offending member relative to `base`
+pub(crate) fn deserialize_tracked<T: DeserializeOwned>(value: JsonApiValue, base: &str) -> Result<T> {
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let mut pointer = base.to_string();
+        for segment in err.path().iter() {
+            match *segment {
+                Segment::Seq { index } => pointer.push_str(&format!("/{}", index)),
+                Segment::Map { ref key } => {
+                    pointer.push('/');
+                    pointer.push_str(&escape_pointer_token(key));
+                }
+                Segment::Enum { .. } | Segment::Unknown => {}
+            }
+        }
+        Error::Parse {
+            pointer: Some(pointer),
+            detail: err.into_inner().to_string(),
+        }
+    })
+}
+
+fn locate_primary_data_error(primary: JsonApiValue) -> Option<Error> {
+    match primary {
+        JsonApiValue::Object(_) => deserialize_tracked::<Resource>(primary, "/data").err(),
+        JsonApiValue::Array(resources) => resources
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, resource)| {
+                deserialize_tracked::<Resource>(resource, &format!("/data/{}", index)).err()
+            })
+            .next(),
+        _ => None,
+    }
+}
+
+fn linkage_identifiers(data: &IdentifierData) -> Vec<&ResourceIdentifier> {
+    match *data {
+        IdentifierData::None => Vec::new(),
+        IdentifierData::Single(ref identifier) => vec![identifier],
+        IdentifierData::Multiple(ref identifiers) => identifiers.iter().collect(),
+    }
+}
+
+impl NewResource {
+    /// This function returns a `Vec` with identified violations of the rules
+    /// for creating a resource of `expected_type`. Set `allow_client_id` if
+    /// the server accepts client-generated ids.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let doc = NewResourceDocument::from_str(r#"{
+    ///   "data": {
+    ///     "type": "photos",
+    ///     "attributes": { "title": "Ember Hamster" }
+    ///   }
+    /// }"#).unwrap();
+    ///
+    /// assert!(doc.data.validate("photos", false).is_none());
+    ///
+    /// match doc.data.validate("articles", false) {
+    ///   Some(errors) => assert_eq!(errors[0].status(), 409),
+    ///   None => assert!(false)
+    /// }
+    /// ```
+    pub fn validate(
+        &self,
+        expected_type: &str,
+        allow_client_id: bool,
+    ) -> Option<Vec<RequestValidationError>> {
+        let mut errors = Vec::<RequestValidationError>::new();
+
+        if self._type.is_empty() {
+            errors.push(RequestValidationError::MissingType);
+        } else if self._type != expected_type {
+            errors.push(RequestValidationError::TypeMismatch(
+                expected_type.to_string(),
+                self._type.clone(),
+            ));
+        }
+
+        if let Some(ref id) = self.id {
+            if !allow_client_id {
+                errors.push(RequestValidationError::ClientIdForbidden);
+            } else if id.is_empty() {
+                errors.push(RequestValidationError::MissingId);
+            }
+        }
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+
+    /// Convert into a `Resource` using the client-generated id if there is one,
+    /// or `id` otherwise
+    pub fn into_resource(self, id: JsonApiId) -> Resource {
+        Resource {
+            _type: self._type,
+            id: self.id.unwrap_or(id),
+            attributes: self.attributes,
+            relationships: self.relationships,
+            links: None,
+            meta: self.meta,
+        }
+    }
+}
+
+impl ResourceUpdate {
+    /// This function returns a `Vec` with identified violations of the rules
+    /// for updating the resource identified by `expected_type` and
+    /// `expected_id` (usually taken from the request URL)
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let doc = ResourceUpdateDocument::from_str(r#"{
+    ///   "data": {
+    ///     "type": "articles",
+    ///     "id": "1",
+    ///     "attributes": { "title": "To TDD or Not" }
+    ///   }
+    /// }"#).unwrap();
+    ///
+    /// assert!(doc.data.validate("articles", "1").is_none());
+    /// assert!(doc.data.validate("articles", "2").is_some());
+    /// ```
+    pub fn validate(
+        &self,
+        expected_type: &str,
+        expected_id: &str,
+    ) -> Option<Vec<RequestValidationError>> {
+        let mut errors = Vec::<RequestValidationError>::new();
+
+        if self._type.is_empty() {
+            errors.push(RequestValidationError::MissingType);
+        } else if self._type != expected_type {
+            errors.push(RequestValidationError::TypeMismatch(
+                expected_type.to_string(),
+                self._type.clone(),
+            ));
+        }
+
+        if self.id.is_empty() {
+            errors.push(RequestValidationError::MissingId);
+        } else if self.id != expected_id {
+            errors.push(RequestValidationError::IdMismatch(
+                expected_id.to_string(),
+                self.id.clone(),
+            ));
+        }
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+}
+
+impl From<ResourceUpdate> for Resource {
+    fn from(update: ResourceUpdate) -> Self {
+        Resource {
+            _type: update._type,
+            id: update.id,
+            attributes: update.attributes,
+            relationships: update.relationships,
+            links: None,
+            meta: update.meta,
+        }
+    }
+}
+
+impl FromStr for NewResourceDocument {
+    type Err = Error;
+
+    /// Instantiate from string
+    fn from_str(s: &str) -> Result<Self> {
+        parse_tracked(s)
+    }
+}
+
+impl FromStr for ResourceUpdateDocument {
+    type Err = Error;
+
+    /// Instantiate from string
+    fn from_str(s: &str) -> Result<Self> {
+        parse_tracked(s)
+    }
+}
+
+impl RequestValidationError {
+    /// The HTTP status code the specification mandates for this violation
+    pub fn status(&self) -> u16 {
+        match *self {
+            RequestValidationError::MissingType | RequestValidationError::MissingId => 400,
+            RequestValidationError::ClientIdForbidden => 403,
+            RequestValidationError::TypeMismatch(_, _)
+            | RequestValidationError::IdMismatch(_, _) => 409,
+        }
+    }
+
+    /// Convert into a `JsonApiError` whose `source.pointer` refers to the
+    /// offending member of the request document
+    pub fn to_jsonapi_error(&self) -> JsonApiError {
+        let (title, detail, pointer) = match *self {
+            RequestValidationError::MissingType => (
+                "Missing type",
+                "The resource object must contain a type".to_string(),
+                "/data/type",
+            ),
+            RequestValidationError::MissingId => (
+                "Missing id",
+                "The resource object must contain an id".to_string(),
+                "/data/id",
+            ),
+            RequestValidationError::ClientIdForbidden => (
+                "Client-generated id not supported",
+                "The server does not accept client-generated ids".to_string(),
+                "/data/id",
+            ),
+            RequestValidationError::TypeMismatch(ref expected, ref found) => (
+                "Type mismatch",
+                format!("Expected type '{}' but found '{}'", expected, found),
+                "/data/type",
+            ),
+            RequestValidationError::IdMismatch(ref expected, ref found) => (
+                "Id mismatch",
+                format!("Expected id '{}' but found '{}'", expected, found),
+                "/data/id",
+            ),
+        };
+
+        JsonApiError {
+            status: Some(self.status().to_string()),
+            title: Some(title.to_string()),
+            detail: Some(detail),
+            source: Some(ErrorSource {
+                pointer: Some(pointer.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl<'a> From<&'a [RequestValidationError]> for DocumentError {
+    fn from(errors: &'a [RequestValidationError]) -> Self {
+        DocumentError {
+            errors: errors.iter().map(|e| e.to_jsonapi_error()).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl JsonApiError {
+    /// The `about` link, leading to further details about this occurrence
+    /// of the problem
+    pub fn about_link(&self) -> Option<&JsonApiValue> {
+        self.links.as_ref().and_then(|links| links.get("about"))
+    }
+
+    /// The `type` link, identifying the type of error this particular
+    /// error is an instance of
+    pub fn type_link(&self) -> Option<&JsonApiValue> {
+        self.links.as_ref().and_then(|links| links.get("type"))
+    }
+}
+
+impl ErrorSource {
+    /// Validate the `pointer` against the request document the error is
+    /// about: it must be a syntactically valid JSON Pointer and point to a
+    /// value that exists in `request`
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let request: JsonApiValue = serde_json::from_str(r#"{
+    ///   "data": { "type": "posts", "attributes": { "title": "" } }
+    /// }"#).unwrap();
+    ///
+    /// let source = ErrorSource {
+    ///     pointer: Some("/data/attributes/title".into()),
+    ///     ..Default::default()
+    /// };
+    /// assert!(source.validate(&request).is_none());
+    ///
+    /// let source = ErrorSource {
+    ///     pointer: Some("/data/attributes/body".into()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     source.validate(&request),
+    ///     Some(vec![ErrorSourceValidationError::UnresolvedPointer("/data/attributes/body".into())])
+    /// );
+    /// ```
+    pub fn validate(&self, request: &JsonApiValue) -> Option<Vec<ErrorSourceValidationError>> {
+        let mut errors = Vec::<ErrorSourceValidationError>::new();
+
+        if let Some(ref pointer) = self.pointer {
+            if !is_json_pointer(pointer) {
+                errors.push(ErrorSourceValidationError::InvalidPointer(pointer.clone()));
+            } else if request.pointer(pointer).is_none() {
+                errors.push(ErrorSourceValidationError::UnresolvedPointer(pointer.clone()));
+            }
+        }
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+}
+
+impl DocumentError {
+    /// Validate the `source` of every error against the request document,
+    /// see [`ErrorSource::validate`](struct.ErrorSource.html#method.validate)
+    pub fn validate_sources(&self, request: &JsonApiValue) -> Option<Vec<ErrorSourceValidationError>> {
+        let errors: Vec<ErrorSourceValidationError> = self
+            .errors
+            .iter()
+            .filter_map(|error| error.source.as_ref())
+            .filter_map(|source| source.validate(request))
+            .flatten()
+            .collect();
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+}
+
+/// Check RFC 6901 syntax: either empty or a sequence of `/`-prefixed
+/// tokens in which `~` is only used in the escapes `~0` and `~1`
+fn is_json_pointer(pointer: &str) -> bool {
+    if pointer.is_empty() {
+        return true;
+    }
+    pointer.starts_with('/')
+        && pointer.split('/').skip(1).all(|token| {
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c == '~' && !matches!(chars.next(), Some('0') | Some('1')) {
+                    return false;
+                }
+            }
+            true
+        })
+}
+
 /// Enum to describe top-level JSON:API specification violations
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum DocumentValidationError {
@@ -485,10 +2140,39 @@ pub enum DocumentValidationError {
     MissingContent,
 }
 
+/// Enum to describe an `ErrorSource` that does not identify a value in the
+/// request document
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorSourceValidationError {
+    InvalidPointer(String),
+    UnresolvedPointer(String),
+}
+
+/// Enum to describe resource linkage violations in a `RelationshipDocument`
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum LinkageValidationError {
+    MissingType,
+    MissingId,
+    DuplicateIdentifier,
+}
+
+/// Enum to describe violations of the rules for create and update request
+/// documents. Mismatches carry the expected and the received value
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestValidationError {
+    MissingType,
+    MissingId,
+    ClientIdForbidden,
+    TypeMismatch(String, String),
+    IdMismatch(String, String),
+}
+
+/// Enum to describe failures to read or write a `Resource` attribute
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub enum JsonApiDataError {
     AttributeNotFound,
     IncompatibleAttributeType,
+    ReservedAttributeName,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -500,13 +2184,27 @@ pub enum RelationshipAssumptionError {
 #[derive(Debug, Clone, PartialEq)]
 pub enum DiffPatchError {
     IncompatibleTypes(String, String),
-    DifferentAttributeKeys,
     NonExistentProperty(String),
     IncorrectPropertyValue(String),
+    NotADataDocument,
 }
 
-#[derive(Debug, Clone, PartialEq, Copy)]
+/// The part of a `Resource` a `Patch` applies to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy)]
 pub enum PatchType {
+    /// A whole `Relationship` object
     Relationship,
+    /// A single resource identifier in a to-many relationship
+    RelationshipMember,
     Attribute,
+    Link,
+    Meta,
+}
+
+/// The kind of change described by a `Patch`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy)]
+pub enum PatchOperation {
+    Add,
+    Remove,
+    Replace,
 }
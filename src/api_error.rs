@@ -0,0 +1,598 @@
+//! Defines conversions between application errors and
+//! [`JsonApiError`](../api/struct.JsonApiError.html) objects: the
+//! [`IntoJsonApiError`](trait.IntoJsonApiError.html) trait for servers and the
+//! [`ErrorRegistry`](struct.ErrorRegistry.html) for clients, and the
+//! [`JsonApiErrorBuilder`](struct.JsonApiErrorBuilder.html) for building
+//! error objects directly
+use std::collections::HashMap;
+use std::fmt;
+use crate::api::*;
+use crate::errors;
+use crate::media_type::NegotiationError;
+use crate::model::JsonApiModel;
+
+/// A trait for application errors that can be reported in a JSON:API error
+/// document. Only the HTTP status is required, the other members of the
+/// error object default to empty.
+///
+/// ```
+/// use jsonapi::api::*;
+/// use jsonapi::api_error::*;
+///
+/// #[derive(Debug)]
+/// enum AppError {
+///     NotFound(String),
+///     OutOfStock,
+/// }
+///
+/// impl IntoJsonApiError for AppError {
+///     fn status(&self) -> u16 {
+///         match *self {
+///             AppError::NotFound(_) => 404,
+///             AppError::OutOfStock => 409,
+///         }
+///     }
+///
+///     fn code(&self) -> Option<String> {
+///         match *self {
+///             AppError::NotFound(_) => Some("not_found".into()),
+///             AppError::OutOfStock => Some("out_of_stock".into()),
+///         }
+///     }
+/// }
+///
+/// let doc = DocumentError::from_errors(vec![AppError::OutOfStock]);
+/// assert_eq!(doc.errors[0].status, Some("409".into()));
+/// assert_eq!(doc.errors[0].code, Some("out_of_stock".into()));
+/// assert_eq!(doc.status(), Some(409));
+/// ```
+pub trait IntoJsonApiError {
+    /// The HTTP status code applicable to this error
+    fn status(&self) -> u16;
+
+    /// An application-specific error code
+    fn code(&self) -> Option<String> {
+        None
+    }
+
+    /// A short, human-readable summary of the problem
+    fn title(&self) -> Option<String> {
+        None
+    }
+
+    /// A human-readable explanation specific to this occurrence of the problem
+    fn detail(&self) -> Option<String> {
+        None
+    }
+
+    /// The part of the request that caused the error. Not named `source`
+    /// so it doesn't clash with `std::error::Error::source`
+    fn error_source(&self) -> Option<ErrorSource> {
+        None
+    }
+
+    fn into_jsonapi_error(self) -> JsonApiError
+    where
+        Self: Sized,
+    {
+        error_object(&self)
+    }
+}
+
+impl IntoJsonApiError for JsonApiError {
+    /// The parsed `status` member, or `500` if it is missing or invalid
+    fn status(&self) -> u16 {
+        self.status
+            .as_ref()
+            .and_then(|status| status.parse().ok())
+            .unwrap_or(500)
+    }
+
+    fn into_jsonapi_error(self) -> JsonApiError {
+        self
+    }
+}
+
+impl IntoJsonApiError for RequestValidationError {
+    fn status(&self) -> u16 {
+        RequestValidationError::status(self)
+    }
+
+    fn into_jsonapi_error(self) -> JsonApiError {
+        self.to_jsonapi_error()
+    }
+}
+
+impl IntoJsonApiError for NegotiationError {
+    fn status(&self) -> u16 {
+        NegotiationError::status(self)
+    }
+
+    fn into_jsonapi_error(self) -> JsonApiError {
+        self.to_jsonapi_error()
+    }
+}
+
+/// Failures caused by the request are client errors, failing to serialize
+/// a model or to write a document is a server error
+impl IntoJsonApiError for errors::Error {
+    fn status(&self) -> u16 {
+        match *self {
+            errors::Error::Parse { .. }
+            | errors::Error::Document(_)
+            | errors::Error::Linkage(_)
+            | errors::Error::Query { .. } => 400,
+            errors::Error::Request(ref errors) => DocumentError::from_errors(errors.clone())
+                .status()
+                .unwrap_or(400),
+            errors::Error::Conversion { .. } => 422,
+            errors::Error::Patch(DiffPatchError::NotADataDocument) => 400,
+            errors::Error::Patch(_) => 409,
+            errors::Error::Json(_) | errors::Error::Io(_) => 500,
+        }
+    }
+
+    fn title(&self) -> Option<String> {
+        let title = match *self {
+            errors::Error::Parse { .. } | errors::Error::Document(_) | errors::Error::Linkage(_) => {
+                "Invalid document"
+            }
+            errors::Error::Request(_) => "Invalid request document",
+            errors::Error::Conversion { .. } => "Invalid resource",
+            errors::Error::Query { .. } => "Invalid query parameter",
+            errors::Error::Patch(_) => "Patch conflict",
+            errors::Error::Json(_) | errors::Error::Io(_) => "Internal Server Error",
+        };
+        Some(title.to_string())
+    }
+
+    fn detail(&self) -> Option<String> {
+        match *self {
+            errors::Error::Parse { ref detail, .. } => Some(detail.clone()),
+            ref error => Some(error.to_string()),
+        }
+    }
+
+    fn error_source(&self) -> Option<ErrorSource> {
+        match *self {
+            errors::Error::Parse { pointer: Some(ref pointer), .. } => Some(ErrorSource {
+                pointer: Some(pointer.clone()),
+                ..Default::default()
+            }),
+            errors::Error::Linkage(_) => Some(ErrorSource {
+                pointer: Some("/data".to_string()),
+                ..Default::default()
+            }),
+            errors::Error::Query { parameter: Some(ref parameter), .. } => Some(ErrorSource {
+                parameter: Some(parameter.clone()),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl errors::Error {
+    /// The error objects describing this error: one per violation for
+    /// validation failures, a single one otherwise
+    pub fn to_jsonapi_errors(&self) -> JsonApiErrors {
+        match *self {
+            errors::Error::Document(ref errors) => errors
+                .iter()
+                .map(|error| {
+                    let (detail, pointer) = match *error {
+                        DocumentValidationError::IncludedWithoutData => {
+                            ("Included resources require primary data", "/included")
+                        }
+                        DocumentValidationError::MissingContent => {
+                            ("A document must contain data or meta", "")
+                        }
+                    };
+                    JsonApiError::builder(HttpStatus::BAD_REQUEST)
+                        .title("Invalid document")
+                        .detail(detail)
+                        .pointer(pointer)
+                        .build()
+                })
+                .collect(),
+            errors::Error::Request(ref errors) => {
+                errors.iter().map(RequestValidationError::to_jsonapi_error).collect()
+            }
+            errors::Error::Linkage(ref errors) => errors
+                .iter()
+                .map(|error| {
+                    let detail = match *error {
+                        LinkageValidationError::MissingType => "Resource linkage is missing a type",
+                        LinkageValidationError::MissingId => "Resource linkage is missing an id",
+                        LinkageValidationError::DuplicateIdentifier => {
+                            "Resource linkage contains duplicate identifiers"
+                        }
+                    };
+                    JsonApiError::builder(HttpStatus::BAD_REQUEST)
+                        .title("Invalid document")
+                        .detail(detail)
+                        .pointer("/data")
+                        .build()
+                })
+                .collect(),
+            ref error => vec![error_object(error)],
+        }
+    }
+}
+
+impl From<errors::Error> for DocumentError {
+    fn from(error: errors::Error) -> Self {
+        DocumentError {
+            errors: error.to_jsonapi_errors(),
+            ..Default::default()
+        }
+    }
+}
+
+fn error_object<E: IntoJsonApiError + ?Sized>(error: &E) -> JsonApiError {
+    JsonApiError {
+        status: Some(error.status().to_string()),
+        code: error.code(),
+        title: error.title(),
+        detail: error.detail(),
+        source: error.error_source(),
+        ..Default::default()
+    }
+}
+
+impl DocumentError {
+    /// Build an error document from application errors
+    pub fn from_errors<E, I>(errors: I) -> Self
+    where
+        E: IntoJsonApiError,
+        I: IntoIterator<Item = E>,
+    {
+        DocumentError {
+            errors: errors.into_iter().map(IntoJsonApiError::into_jsonapi_error).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// The HTTP status code to respond with. If all errors share a status
+    /// that status is used, otherwise the most generally applicable one:
+    /// `400` if all are client errors and `500` if not. Returns `None` if
+    /// there are no errors.
+    pub fn status(&self) -> Option<u16> {
+        let statuses: Vec<u16> = self.errors.iter().map(IntoJsonApiError::status).collect();
+        let first = *statuses.first()?;
+        if statuses.iter().all(|status| *status == first) {
+            Some(first)
+        } else if statuses.iter().all(|status| (400..500).contains(status)) {
+            Some(400)
+        } else {
+            Some(500)
+        }
+    }
+}
+
+type ErrorMapper<E> = Box<dyn Fn(&JsonApiError) -> E + Send + Sync>;
+
+/// Maps the error objects in a JSON:API error document to application
+/// errors. Mappings are registered per error `code`; errors without a
+/// registered code are handed to the fallback.
+///
+/// ```
+/// use jsonapi::api::*;
+/// use jsonapi::api_error::*;
+/// use std::str::FromStr;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum AppError {
+///     OutOfStock,
+///     Other(Option<String>),
+/// }
+///
+/// let registry = ErrorRegistry::new(|e: &JsonApiError| AppError::Other(e.detail.clone()))
+///     .register("out_of_stock", |_| AppError::OutOfStock);
+///
+/// let doc = JsonApiDocument::from_str(r#"{
+///   "errors": [{ "status": "409", "code": "out_of_stock" }]
+/// }"#).unwrap();
+///
+/// match doc {
+///     JsonApiDocument::Error(ref errors) => {
+///         assert_eq!(registry.map_errors(errors), vec![AppError::OutOfStock]);
+///     }
+///     _ => assert!(false),
+/// }
+/// ```
+pub struct ErrorRegistry<E> {
+    mappers: HashMap<String, ErrorMapper<E>>,
+    fallback: ErrorMapper<E>,
+}
+
+impl<E> ErrorRegistry<E> {
+    /// Create a registry that maps every error with `fallback`
+    pub fn new<F>(fallback: F) -> Self
+    where
+        F: Fn(&JsonApiError) -> E + Send + Sync + 'static,
+    {
+        ErrorRegistry {
+            mappers: HashMap::new(),
+            fallback: Box::new(fallback),
+        }
+    }
+
+    /// Map errors whose `code` is `code` with `mapper`
+    pub fn register<F>(mut self, code: &str, mapper: F) -> Self
+    where
+        F: Fn(&JsonApiError) -> E + Send + Sync + 'static,
+    {
+        self.mappers.insert(code.to_string(), Box::new(mapper));
+        self
+    }
+
+    /// Map a single error object
+    pub fn map_error(&self, error: &JsonApiError) -> E {
+        let mapper = error
+            .code
+            .as_ref()
+            .and_then(|code| self.mappers.get(code))
+            .unwrap_or(&self.fallback);
+        mapper(error)
+    }
+
+    /// Map every error object in an error document
+    pub fn map_errors(&self, doc: &DocumentError) -> Vec<E> {
+        doc.errors.iter().map(|error| self.map_error(error)).collect()
+    }
+
+    /// Turn a response document into a model or an application error. For an
+    /// error document the first error object is mapped; a document without
+    /// any error objects is mapped as if it held an empty one.
+    pub fn into_result<T: JsonApiModel>(&self, doc: &JsonApiDocument) -> Result<T, E>
+    where
+        E: From<errors::Error>,
+    {
+        match *doc {
+            JsonApiDocument::Data(ref data) => T::from_jsonapi_document(data).map_err(E::from),
+            JsonApiDocument::Error(ref errors) => match errors.errors.first() {
+                Some(error) => Err(self.map_error(error)),
+                None => Err(self.map_error(&JsonApiError::default())),
+            },
+        }
+    }
+}
+
+impl<E> fmt::Debug for ErrorRegistry<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut codes: Vec<&String> = self.mappers.keys().collect();
+        codes.sort();
+        f.debug_struct("ErrorRegistry").field("codes", &codes).finish()
+    }
+}
+
+/// An HTTP status code applicable to an error, in the range `400..=599`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HttpStatus(u16);
+
+impl HttpStatus {
+    pub const BAD_REQUEST: HttpStatus = HttpStatus(400);
+    pub const UNAUTHORIZED: HttpStatus = HttpStatus(401);
+    pub const FORBIDDEN: HttpStatus = HttpStatus(403);
+    pub const NOT_FOUND: HttpStatus = HttpStatus(404);
+    pub const METHOD_NOT_ALLOWED: HttpStatus = HttpStatus(405);
+    pub const NOT_ACCEPTABLE: HttpStatus = HttpStatus(406);
+    pub const CONFLICT: HttpStatus = HttpStatus(409);
+    pub const PRECONDITION_FAILED: HttpStatus = HttpStatus(412);
+    pub const UNSUPPORTED_MEDIA_TYPE: HttpStatus = HttpStatus(415);
+    pub const UNPROCESSABLE_ENTITY: HttpStatus = HttpStatus(422);
+    pub const INTERNAL_SERVER_ERROR: HttpStatus = HttpStatus(500);
+    pub const SERVICE_UNAVAILABLE: HttpStatus = HttpStatus(503);
+
+    /// Returns `None` if `status` is not a client or server error status
+    pub fn new(status: u16) -> Option<Self> {
+        match status {
+            400..=599 => Some(HttpStatus(status)),
+            _ => None,
+        }
+    }
+
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+
+    /// The reason phrase for well-known statuses
+    pub fn canonical_reason(self) -> Option<&'static str> {
+        match self.0 {
+            400 => Some("Bad Request"),
+            401 => Some("Unauthorized"),
+            403 => Some("Forbidden"),
+            404 => Some("Not Found"),
+            405 => Some("Method Not Allowed"),
+            406 => Some("Not Acceptable"),
+            409 => Some("Conflict"),
+            412 => Some("Precondition Failed"),
+            415 => Some("Unsupported Media Type"),
+            422 => Some("Unprocessable Entity"),
+            500 => Some("Internal Server Error"),
+            503 => Some("Service Unavailable"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Builds a `JsonApiError` with a typed HTTP status. Unless set explicitly
+/// the `title` is the reason phrase of the status.
+///
+/// ```
+/// use jsonapi::api::*;
+/// use jsonapi::api_error::*;
+///
+/// let error = JsonApiError::builder(HttpStatus::UNPROCESSABLE_ENTITY)
+///     .code("too_short")
+///     .detail("Title must be at least 3 characters")
+///     .pointer("/data/attributes/title")
+///     .build();
+///
+/// assert_eq!(error.status, Some("422".into()));
+/// assert_eq!(error.title, Some("Unprocessable Entity".into()));
+/// assert_eq!(error.source.unwrap().pointer, Some("/data/attributes/title".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApiErrorBuilder {
+    status: HttpStatus,
+    error: JsonApiError,
+}
+
+impl JsonApiError {
+    pub fn builder(status: HttpStatus) -> JsonApiErrorBuilder {
+        JsonApiErrorBuilder {
+            status,
+            error: JsonApiError::default(),
+        }
+    }
+}
+
+impl JsonApiErrorBuilder {
+    /// A unique identifier for this occurrence of the problem
+    pub fn id(mut self, id: &str) -> Self {
+        self.error.id = Some(id.to_string());
+        self
+    }
+
+    pub fn code(mut self, code: &str) -> Self {
+        self.error.code = Some(code.to_string());
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.error.title = Some(title.to_string());
+        self
+    }
+
+    pub fn detail(mut self, detail: &str) -> Self {
+        self.error.detail = Some(detail.to_string());
+        self
+    }
+
+    /// A JSON Pointer to the value in the request document that caused the
+    /// error
+    pub fn pointer(mut self, pointer: &str) -> Self {
+        self.source().pointer = Some(pointer.to_string());
+        self
+    }
+
+    /// The query parameter that caused the error
+    pub fn parameter(mut self, parameter: &str) -> Self {
+        self.source().parameter = Some(parameter.to_string());
+        self
+    }
+
+    /// The request header that caused the error
+    pub fn header(mut self, header: &str) -> Self {
+        self.source().header = Some(header.to_string());
+        self
+    }
+
+    /// A link that leads to further details about this occurrence
+    pub fn about(mut self, href: &str) -> Self {
+        self.error
+            .links
+            .get_or_insert_with(Links::new)
+            .insert("about".to_string(), JsonApiValue::String(href.to_string()));
+        self
+    }
+
+    /// A link that identifies the type of error this is an instance of
+    pub fn type_link(mut self, href: &str) -> Self {
+        self.error
+            .links
+            .get_or_insert_with(Links::new)
+            .insert("type".to_string(), JsonApiValue::String(href.to_string()));
+        self
+    }
+
+    pub fn meta(mut self, key: &str, value: JsonApiValue) -> Self {
+        self.error
+            .meta
+            .get_or_insert_with(Meta::new)
+            .insert(key.to_string(), value);
+        self
+    }
+
+    pub fn build(self) -> JsonApiError {
+        let mut error = self.error;
+        error.status = Some(self.status.to_string());
+        if error.title.is_none() {
+            error.title = self.status.canonical_reason().map(str::to_string);
+        }
+        error
+    }
+
+    fn source(&mut self) -> &mut ErrorSource {
+        self.error.source.get_or_insert_with(ErrorSource::default)
+    }
+}
+
+impl From<JsonApiErrorBuilder> for JsonApiError {
+    fn from(builder: JsonApiErrorBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Constructors for error documents in common situations
+impl DocumentError {
+    /// A `404` for a resource that does not exist
+    pub fn not_found(resource_type: &str, id: &str) -> Self {
+        DocumentError::from_errors(vec![JsonApiError::builder(HttpStatus::NOT_FOUND)
+            .detail(&format!("Resource of type '{}' with id '{}' not found", resource_type, id))
+            .build()])
+    }
+
+    /// A `422` with one error per invalid attribute, given as pairs of
+    /// attribute name and detail. Each error points at the attribute in the
+    /// request document.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    ///
+    /// let doc = DocumentError::validation_failed(vec![
+    ///     ("title", "Title can't be blank"),
+    ///     ("a/b", "Invalid"),
+    /// ]);
+    /// let pointers: Vec<_> = doc.errors.iter()
+    ///     .map(|e| e.source.as_ref().unwrap().pointer.clone().unwrap())
+    ///     .collect();
+    /// assert_eq!(pointers, vec!["/data/attributes/title", "/data/attributes/a~1b"]);
+    /// ```
+    pub fn validation_failed<'a, I>(failures: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        DocumentError::from_errors(failures.into_iter().map(|(attribute, detail)| {
+            JsonApiError::builder(HttpStatus::UNPROCESSABLE_ENTITY)
+                .detail(detail)
+                .pointer(&format!("/data/attributes/{}", escape_pointer_token(attribute)))
+                .build()
+        }))
+    }
+
+    /// A `409` for a request that conflicts with the current state of the
+    /// server
+    pub fn conflict(detail: &str) -> Self {
+        DocumentError::from_errors(vec![JsonApiError::builder(HttpStatus::CONFLICT)
+            .detail(detail)
+            .build()])
+    }
+
+    /// A `400` for an `include` path the server does not support
+    pub fn unsupported_include(path: &str) -> Self {
+        DocumentError::from_errors(vec![JsonApiError::builder(HttpStatus::BAD_REQUEST)
+            .title("Unsupported include")
+            .detail(&format!("Including '{}' is not supported", path))
+            .parameter("include")
+            .build()])
+    }
+}
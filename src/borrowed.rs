@@ -0,0 +1,377 @@
+//! Borrowed counterparts of the document types in [`api`](../api/index.html)
+//! for read-heavy code paths. Types and ids borrow from the input, and
+//! attributes, links and meta are kept as unparsed JSON
+//! ([`RawValue`](https://docs.rs/serde_json/latest/serde_json/value/struct.RawValue.html))
+//! until they are needed. Convert to the owned types with
+//! [`ResourceRef::to_resource`](struct.ResourceRef.html#method.to_resource)
+//! and [`DocumentRef::to_document`](struct.DocumentRef.html#method.to_document).
+//!
+//! Only data documents are supported, parse error documents with
+//! [`JsonApiDocument`](../api/enum.JsonApiDocument.html).
+//!
+//! ```
+//! use jsonapi::borrowed::*;
+//!
+//! let input = r#"{
+//!   "data": [
+//!     { "type": "fleas", "id": "1", "attributes": { "name": "rick", "legs": 6 } },
+//!     { "type": "fleas", "id": "2", "attributes": { "name": "morty", "legs": 6 } }
+//!   ]
+//! }"#;
+//!
+//! let doc = DocumentRef::from_str(input).unwrap();
+//! let names: Vec<&str> = doc
+//!     .primary_resources()
+//!     .iter()
+//!     .map(|resource| resource.attribute::<&str>("name").unwrap().unwrap())
+//!     .collect();
+//! assert_eq!(names, vec!["rick", "morty"]);
+//! ```
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use crate::api::*;
+use crate::map::JsonApiMap;
+use crate::errors::{Error, Result};
+
+/// Attributes of a [`ResourceRef`](struct.ResourceRef.html), by name, in the
+/// same order as [`ResourceAttributes`](../api/type.ResourceAttributes.html)
+pub type AttributesRef<'a> = JsonApiMap<&'a RawValue, Cow<'a, str>>;
+
+/// Relationships of a [`ResourceRef`](struct.ResourceRef.html), by name
+pub type RelationshipsRef<'a> = JsonApiMap<RelationshipRef<'a>, Cow<'a, str>>;
+
+/// Borrowed [`ResourceIdentifier`](../api/struct.ResourceIdentifier.html)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResourceIdentifierRef<'a> {
+    #[serde(rename = "type", borrow)]
+    pub _type: Cow<'a, str>,
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+}
+
+/// Borrowed [`IdentifierData`](../api/enum.IdentifierData.html)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IdentifierDataRef<'a> {
+    None,
+    #[serde(borrow)]
+    Single(ResourceIdentifierRef<'a>),
+    #[serde(borrow)]
+    Multiple(Vec<ResourceIdentifierRef<'a>>),
+}
+
+/// Borrowed [`Relationship`](../api/struct.Relationship.html)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RelationshipRef<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub data: Option<IdentifierDataRef<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub links: Option<&'a RawValue>,
+}
+
+/// Borrowed [`Resource`](../api/struct.Resource.html)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResourceRef<'a> {
+    #[serde(rename = "type", borrow)]
+    pub _type: Cow<'a, str>,
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow, default, deserialize_with = "borrowed_keys")]
+    pub attributes: AttributesRef<'a>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "optional_borrowed_keys",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub relationships: Option<RelationshipsRef<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub links: Option<&'a RawValue>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<&'a RawValue>,
+}
+
+/// Borrowed [`PrimaryData`](../api/enum.PrimaryData.html)
+#[derive(Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum PrimaryDataRef<'a> {
+    None,
+    Single(Box<ResourceRef<'a>>),
+    Multiple(Vec<ResourceRef<'a>>),
+}
+
+/// Borrowed [`DocumentData`](../api/struct.DocumentData.html)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DocumentRef<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub data: Option<PrimaryDataRef<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub included: Option<Vec<ResourceRef<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub links: Option<&'a RawValue>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<&'a RawValue>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<&'a RawValue>,
+}
+
+/// `#[serde(untagged)]` buffers the input, which `RawValue` does not
+/// support, so the variant is chosen by hand
+impl<'de: 'a, 'a> Deserialize<'de> for PrimaryDataRef<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+        struct PrimaryDataVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for PrimaryDataVisitor<'a> {
+            type Value = PrimaryDataRef<'a>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("null, a resource object or an array of resource objects")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> ::std::result::Result<Self::Value, E> {
+                Ok(PrimaryDataRef::None)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> ::std::result::Result<Self::Value, A::Error> {
+                ResourceRef::deserialize(MapAccessDeserializer::new(map))
+                    .map(|resource| PrimaryDataRef::Single(Box::new(resource)))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> ::std::result::Result<Self::Value, A::Error> {
+                Vec::deserialize(SeqAccessDeserializer::new(seq)).map(PrimaryDataRef::Multiple)
+            }
+        }
+
+        deserializer.deserialize_any(PrimaryDataVisitor(PhantomData))
+    }
+}
+
+/// A map key that borrows from the input unless it contains escape sequences
+#[derive(Deserialize)]
+struct Key<'a>(#[serde(borrow)] Cow<'a, str>);
+
+/// `JsonApiMap<_, Cow<str>>` always deserializes owned keys
+fn borrowed_keys<'de: 'a, 'a, D, V>(deserializer: D) -> ::std::result::Result<JsonApiMap<V, Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    struct MapVisitor<'a, V>(PhantomData<(&'a (), V)>);
+
+    impl<'de: 'a, 'a, V: Deserialize<'de>> Visitor<'de> for MapVisitor<'a, V> {
+        type Value = JsonApiMap<V, Cow<'a, str>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an object")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error> {
+            let mut values = JsonApiMap::new();
+            while let Some((Key(key), value)) = map.next_entry::<Key, V>()? {
+                values.insert(key, value);
+            }
+            Ok(values)
+        }
+    }
+
+    deserializer.deserialize_map(MapVisitor(PhantomData))
+}
+
+fn optional_borrowed_keys<'de: 'a, 'a, D, V>(
+    deserializer: D,
+) -> ::std::result::Result<Option<JsonApiMap<V, Cow<'a, str>>>, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    struct OptionVisitor<'a, V>(PhantomData<(&'a (), V)>);
+
+    impl<'de: 'a, 'a, V: Deserialize<'de>> Visitor<'de> for OptionVisitor<'a, V> {
+        type Value = Option<JsonApiMap<V, Cow<'a, str>>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an object or null")
+        }
+
+        fn visit_none<E: de::Error>(self) -> ::std::result::Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> ::std::result::Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> ::std::result::Result<Self::Value, D::Error> {
+            borrowed_keys(deserializer).map(Some)
+        }
+    }
+
+    deserializer.deserialize_option(OptionVisitor(PhantomData))
+}
+
+/// Prefix the pointer of parse errors with `base`, the location of the
+/// resource being converted
+fn located(base: String) -> impl FnOnce(Error) -> Error {
+    move |err| match err {
+        Error::Parse { pointer: Some(pointer), detail } => Error::Parse {
+            pointer: Some(format!("{}{}", base, pointer)),
+            detail,
+        },
+        err => err,
+    }
+}
+
+/// Parse the unparsed JSON of a member into an owned type
+fn parse_raw<T: DeserializeOwned>(raw: &RawValue, pointer: &str) -> Result<T> {
+    serde_json::from_str(raw.get()).map_err(|err| Error::Parse {
+        pointer: Some(pointer.to_string()),
+        detail: err.to_string(),
+    })
+}
+
+impl<'a> ResourceIdentifierRef<'a> {
+    pub fn to_identifier(&self) -> ResourceIdentifier {
+        ResourceIdentifier {
+            _type: self._type.to_string(),
+            id: self.id.to_string(),
+        }
+    }
+}
+
+impl<'a> IdentifierDataRef<'a> {
+    pub fn to_identifier_data(&self) -> IdentifierData {
+        match *self {
+            IdentifierDataRef::None => IdentifierData::None,
+            IdentifierDataRef::Single(ref identifier) => IdentifierData::Single(identifier.to_identifier()),
+            IdentifierDataRef::Multiple(ref identifiers) => {
+                IdentifierData::Multiple(identifiers.iter().map(ResourceIdentifierRef::to_identifier).collect())
+            }
+        }
+    }
+}
+
+impl<'a> ResourceRef<'a> {
+    /// Deserialize the attribute `name`, `None` if the resource has no such
+    /// attribute. `T` can borrow from the input, e.g. `&str` for string
+    /// attributes without escape sequences.
+    pub fn attribute<T: Deserialize<'a>>(&self, name: &str) -> Result<Option<T>> {
+        match self.attributes.get(name) {
+            Some(&raw) => serde_json::from_str(raw.get()).map(Some).map_err(|err| Error::Parse {
+                pointer: Some(format!("/attributes/{}", escape_pointer_token(name))),
+                detail: err.to_string(),
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// The resource linkage of the relationship `name`
+    pub fn relationship(&self, name: &str) -> Option<&RelationshipRef<'a>> {
+        self.relationships.as_ref().and_then(|relationships| relationships.get(name))
+    }
+
+    /// Convert into an owned `Resource`, parsing the attributes, links and
+    /// meta
+    pub fn to_resource(&self) -> Result<Resource> {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(name, raw)| {
+                let pointer = format!("/attributes/{}", escape_pointer_token(name));
+                Ok((name.to_string(), parse_raw(raw, &pointer)?))
+            })
+            .collect::<Result<ResourceAttributes>>()?;
+        let relationships = match self.relationships {
+            Some(ref relationships) => Some(
+                relationships
+                    .iter()
+                    .map(|(name, relationship)| {
+                        let links = match relationship.links {
+                            Some(raw) => {
+                                let pointer = format!("/relationships/{}/links", escape_pointer_token(name));
+                                Some(parse_raw(raw, &pointer)?)
+                            }
+                            None => None,
+                        };
+                        let relationship = Relationship {
+                            data: relationship.data.as_ref().map(IdentifierDataRef::to_identifier_data),
+                            links,
+                        };
+                        Ok((name.to_string(), relationship))
+                    })
+                    .collect::<Result<Relationships>>()?,
+            ),
+            None => None,
+        };
+
+        Ok(Resource {
+            _type: self._type.to_string(),
+            id: self.id.to_string(),
+            attributes,
+            relationships,
+            links: self.links.map(|raw| parse_raw(raw, "/links")).transpose()?,
+            meta: self.meta.map(|raw| parse_raw(raw, "/meta")).transpose()?,
+        })
+    }
+}
+
+impl<'a> DocumentRef<'a> {
+    /// Parse a data document, borrowing from `s`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> Result<Self> {
+        serde_json::from_str(s).map_err(|err| Error::Parse {
+            pointer: None,
+            detail: err.to_string(),
+        })
+    }
+
+    /// The resources in the primary data as a slice, whether the document
+    /// holds a single resource or a collection
+    pub fn primary_resources(&self) -> &[ResourceRef<'a>] {
+        match self.data {
+            Some(PrimaryDataRef::Single(ref resource)) => std::slice::from_ref(resource.as_ref()),
+            Some(PrimaryDataRef::Multiple(ref resources)) => resources,
+            Some(PrimaryDataRef::None) | None => &[],
+        }
+    }
+
+    /// Convert into an owned `DocumentData`
+    pub fn to_document(&self) -> Result<DocumentData> {
+        let data = match self.data {
+            Some(PrimaryDataRef::None) => Some(PrimaryData::None),
+            Some(PrimaryDataRef::Single(ref resource)) => Some(PrimaryData::Single(Box::new(
+                resource.to_resource().map_err(located("/data".to_string()))?,
+            ))),
+            Some(PrimaryDataRef::Multiple(ref resources)) => Some(PrimaryData::Multiple(
+                resources
+                    .iter()
+                    .enumerate()
+                    .map(|(index, resource)| resource.to_resource().map_err(located(format!("/data/{}", index))))
+                    .collect::<Result<Resources>>()?,
+            )),
+            None => None,
+        };
+        let included = match self.included {
+            Some(ref included) => Some(
+                included
+                    .iter()
+                    .enumerate()
+                    .map(|(index, resource)| resource.to_resource().map_err(located(format!("/included/{}", index))))
+                    .collect::<Result<Resources>>()?,
+            ),
+            None => None,
+        };
+
+        Ok(DocumentData {
+            data,
+            included,
+            links: self.links.map(|raw| parse_raw(raw, "/links")).transpose()?,
+            meta: self.meta.map(|raw| parse_raw(raw, "/meta")).transpose()?,
+            jsonapi: self.jsonapi.map(|raw| parse_raw(raw, "/jsonapi")).transpose()?,
+        })
+    }
+}
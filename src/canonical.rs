@@ -0,0 +1,75 @@
+//! Canonical serialization, for output that has to be byte-identical for
+//! equal documents such as snapshots, signatures and cache keys.
+//!
+//! Canonical output is compact JSON with the members of every object sorted
+//! by name, so the order in which members were inserted or parsed does not
+//! matter.
+//!
+//! ```
+//! # extern crate jsonapi;
+//! use jsonapi::api::*;
+//! use jsonapi::canonical::to_canonical_string;
+//! use std::str::FromStr;
+//!
+//! let a = JsonApiDocument::from_str(r#"{ "meta": { "b": 1, "a": [2, { "d": 3, "c": 4 }] } }"#).unwrap();
+//! let b = JsonApiDocument::from_str(r#"{ "meta": { "a": [2, { "c": 4, "d": 3 }], "b": 1 } }"#).unwrap();
+//! assert_eq!(to_canonical_string(&a).unwrap(), r#"{"meta":{"a":[2,{"c":4,"d":3}],"b":1}}"#);
+//! assert_eq!(to_canonical_string(&a).unwrap(), to_canonical_string(&b).unwrap());
+//! ```
+use std::io::Write;
+use serde::Serialize;
+use serde_json::Value;
+use crate::errors::Result;
+
+/// Serialize `value` as canonical JSON into `writer`
+pub fn to_canonical_writer<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    write_value(&mut writer, &serde_json::to_value(value)?)
+}
+
+/// Serialize `value` as canonical JSON bytes
+pub fn to_canonical_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    to_canonical_writer(&mut output, value)?;
+    Ok(output)
+}
+
+/// Serialize `value` as a canonical JSON string
+pub fn to_canonical_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let output = to_canonical_vec(value)?;
+    Ok(String::from_utf8(output).expect("serde_json writes valid UTF-8"))
+}
+
+fn write_value<W: Write>(writer: &mut W, value: &Value) -> Result<()> {
+    match *value {
+        Value::Array(ref items) => {
+            writer.write_all(b"[")?;
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    writer.write_all(b",")?;
+                }
+                write_value(writer, item)?;
+            }
+            writer.write_all(b"]")?;
+        }
+        Value::Object(ref members) => {
+            let mut members: Vec<(&String, &Value)> = members.iter().collect();
+            members.sort_by(|a, b| a.0.cmp(b.0));
+            writer.write_all(b"{")?;
+            for (idx, (name, member)) in members.into_iter().enumerate() {
+                if idx > 0 {
+                    writer.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut *writer, name)?;
+                writer.write_all(b":")?;
+                write_value(writer, member)?;
+            }
+            writer.write_all(b"}")?;
+        }
+        ref scalar => serde_json::to_writer(&mut *writer, scalar)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,561 @@
+//! A client for consuming JSON:API servers, enabled with the `client` cargo
+//! feature.
+//!
+//! The client builds URLs from a [`Query`](../query/struct.Query.html),
+//! decodes responses into [`JsonApiModel`](../model/trait.JsonApiModel.html)s
+//! resolving relationships against the `included` resources, follows `next`
+//! links to iterate over paginated collections and turns error documents into
+//! [`ClientError::Document`](enum.ClientError.html#variant.Document).
+//!
+//! HTTP is left to a [`Transport`](trait.Transport.html), so any HTTP library
+//! can be plugged in. [`MockTransport`](struct.MockTransport.html) answers
+//! requests with canned responses for testing.
+//!
+//! ```
+//! #[macro_use] extern crate serde_derive;
+//! #[macro_use] extern crate jsonapi;
+//! use jsonapi::client::*;
+//! use jsonapi::model::*;
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Dog {
+//!     id: String,
+//!     name: String,
+//! }
+//! jsonapi_model!(Dog; "dogs");
+//!
+//! let transport = MockTransport::new()
+//!     .respond(Method::Get, "https://example.com/dogs/1", 200, r#"{
+//!         "data": { "type": "dogs", "id": "1", "attributes": { "name": "Rex" } }
+//!     }"#)
+//!     .respond(Method::Get, "https://example.com/dogs/2", 404, r#"{
+//!         "errors": [{ "status": "404", "title": "Not Found" }]
+//!     }"#);
+//! let client = Client::new("https://example.com", transport);
+//!
+//! let dog: Dog = client.get("/dogs/1", None).unwrap();
+//! assert_eq!(dog.name, "Rex");
+//!
+//! let error = client.get::<Dog>("/dogs/2", None).unwrap_err();
+//! assert_eq!(error.status(), Some(404));
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+use std::vec;
+use crate::api::*;
+use crate::errors;
+use crate::media_type::JSONAPI_MEDIA_TYPE;
+use crate::model::JsonApiModel;
+use crate::query::Query;
+use url::Url;
+
+/// The HTTP methods used by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+    Patch,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An HTTP request for a [`Transport`](trait.Transport.html) to send
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl Request {
+    /// The value of the first header called `name`, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// The HTTP response a [`Transport`](trait.Transport.html) received
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new<B: Into<Vec<u8>>>(status: u16, body: B) -> Self {
+        Response {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    /// The value of the first header called `name`, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Sends HTTP requests on behalf of a [`Client`](struct.Client.html).
+/// Implement it for the HTTP library of your choice; closures taking a
+/// `Request` implement it too.
+pub trait Transport {
+    /// Send `request` and return the response, whatever its status. Only
+    /// failures to get a response at all are errors.
+    fn send(&self, request: Request) -> Result<Response, TransportError>;
+}
+
+impl<F> Transport for F
+where
+    F: Fn(Request) -> Result<Response, TransportError>,
+{
+    fn send(&self, request: Request) -> Result<Response, TransportError> {
+        self(request)
+    }
+}
+
+/// A failure to get a response from the server, e.g. a connection error
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransportError(pub String);
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl error::Error for TransportError {}
+
+/// Enum to describe why a request made by the client failed
+#[derive(Debug)]
+pub enum ClientError {
+    /// No response was received
+    Transport(TransportError),
+    /// The server responded with an error document
+    Document {
+        status: u16,
+        errors: Box<DocumentError>,
+    },
+    /// The server responded with an unsuccessful status and no error document
+    Status { status: u16, body: String },
+    /// The response is not a JSON:API document or could not be converted
+    Decode(errors::Error),
+}
+
+impl ClientError {
+    /// The HTTP status of the response, if there was one
+    pub fn status(&self) -> Option<u16> {
+        match *self {
+            ClientError::Document { status, .. } | ClientError::Status { status, .. } => Some(status),
+            ClientError::Transport(_) | ClientError::Decode(_) => None,
+        }
+    }
+
+    /// The error document the server responded with
+    pub fn errors(&self) -> Option<&DocumentError> {
+        match *self {
+            ClientError::Document { ref errors, .. } => Some(errors),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClientError::Transport(ref err) => write!(f, "Request failed: {}", err),
+            ClientError::Document { status, ref errors } => {
+                let details: Vec<&str> = errors
+                    .errors
+                    .iter()
+                    .filter_map(|error| error.detail.as_ref().or(error.title.as_ref()))
+                    .map(String::as_str)
+                    .collect();
+                write!(f, "Server responded with {}: {}", status, details.join(", "))
+            }
+            ClientError::Status { status, .. } => write!(f, "Server responded with {}", status),
+            ClientError::Decode(ref err) => write!(f, "Invalid response: {}", err),
+        }
+    }
+}
+
+impl error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ClientError::Transport(ref err) => Some(err),
+            ClientError::Decode(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<TransportError> for ClientError {
+    fn from(err: TransportError) -> Self {
+        ClientError::Transport(err)
+    }
+}
+
+impl From<errors::Error> for ClientError {
+    fn from(err: errors::Error) -> Self {
+        ClientError::Decode(err)
+    }
+}
+
+/// A client for a JSON:API server at a base URL
+#[derive(Debug, Clone)]
+pub struct Client<T> {
+    base_url: String,
+    headers: Vec<(String, String)>,
+    transport: T,
+}
+
+impl<T: Transport> Client<T> {
+    pub fn new(base_url: &str, transport: T) -> Self {
+        Client {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers: vec![],
+            transport,
+        }
+    }
+
+    /// Send the header with every request, e.g. for authentication
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// The URL of `path` relative to the base URL, with the parameters of
+    /// `query`
+    pub fn url(&self, path: &str, query: Option<&Query>) -> String {
+        let mut url = self.resolve(path);
+        if let Some(params) = query.map(Query::to_params).filter(|params| !params.is_empty()) {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&params);
+        }
+        url
+    }
+
+    /// Resolve a path against the base URL. Absolute URLs are kept, paths
+    /// are relative to the base URL whether or not they start with a `/`
+    fn resolve(&self, link: &str) -> String {
+        if link.starts_with("http://") || link.starts_with("https://") {
+            link.to_string()
+        } else if link.starts_with('/') {
+            format!("{}{}", self.base_url, link)
+        } else {
+            format!("{}/{}", self.base_url, link)
+        }
+    }
+
+    /// Fetch the document at `path`
+    pub fn fetch(&self, path: &str, query: Option<&Query>) -> Result<DocumentData, ClientError> {
+        self.fetch_url(&self.url(path, query))
+    }
+
+    fn fetch_url(&self, url: &str) -> Result<DocumentData, ClientError> {
+        let response = self.send(Method::Get, url, None)?;
+        document_data(&response)
+    }
+
+    /// Fetch the resource at `path` as a model, resolving its relationships
+    /// against the `included` resources
+    pub fn get<M: JsonApiModel>(&self, path: &str, query: Option<&Query>) -> Result<M, ClientError> {
+        let doc = self.fetch(path, query)?;
+        match doc.data {
+            Some(PrimaryData::Single(ref resource)) => {
+                Ok(M::from_jsonapi_resource_with(resource, &IncludedIndex::from_document(&doc))?)
+            }
+            _ => Err(ClientError::Decode(errors::Error::Parse {
+                pointer: Some("/data".into()),
+                detail: "Expected a single resource".into(),
+            })),
+        }
+    }
+
+    /// Fetch the first page of the collection at `path` as models
+    pub fn list<M: JsonApiModel>(&self, path: &str, query: Option<&Query>) -> Result<Vec<M>, ClientError> {
+        Ok(models(&self.fetch(path, query)?)?)
+    }
+
+    /// Iterate over the pages of the collection at `path`, following the
+    /// `next` link of every page. Iteration stops after the last page or the
+    /// first error.
+    pub fn pages(&self, path: &str, query: Option<&Query>) -> Pages<'_, T> {
+        Pages {
+            client: self,
+            next: Some(Ok(self.url(path, query))),
+        }
+    }
+
+    /// Iterate over the models of every page of the collection at `path`,
+    /// fetching pages as they are needed
+    pub fn stream<M: JsonApiModel>(&self, path: &str, query: Option<&Query>) -> ModelStream<'_, T, M> {
+        ModelStream {
+            pages: self.pages(path, query),
+            models: Vec::new().into_iter(),
+        }
+    }
+
+    /// `POST` the model to the collection at `path` and return the created
+    /// resource, or `None` if the server responded without a body, e.g.
+    /// `204 No Content` for a resource with a client-generated id
+    pub fn create<M: JsonApiModel>(&self, path: &str, model: &M) -> Result<Option<M>, ClientError> {
+        let (resource, _) = model.to_jsonapi_resource();
+        let doc = NewResourceDocument {
+            data: NewResource {
+                id: Some(resource.id).filter(|id| !id.is_empty()),
+                _type: resource._type,
+                attributes: resource.attributes,
+                relationships: resource.relationships,
+                meta: resource.meta,
+            },
+            ..Default::default()
+        };
+        self.send_model(Method::Post, path, &doc)
+    }
+
+    /// `PATCH` the resource at `path` with the model and return the updated
+    /// resource, or `None` if the server responded without a body, e.g.
+    /// `204 No Content` when the update was accepted as sent
+    pub fn update<M: JsonApiModel>(&self, path: &str, model: &M) -> Result<Option<M>, ClientError> {
+        let (resource, _) = model.to_jsonapi_resource();
+        let doc = ResourceUpdateDocument {
+            data: ResourceUpdate {
+                _type: resource._type,
+                id: resource.id,
+                attributes: resource.attributes,
+                relationships: resource.relationships,
+                meta: resource.meta,
+            },
+            ..Default::default()
+        };
+        self.send_model(Method::Patch, path, &doc)
+    }
+
+    /// `DELETE` the resource at `path`
+    pub fn delete(&self, path: &str) -> Result<(), ClientError> {
+        self.send(Method::Delete, &self.url(path, None), None)?;
+        Ok(())
+    }
+
+    /// Send a request document, which carries the primary resource only, and
+    /// decode the resource in the response
+    fn send_model<M, D>(&self, method: Method, path: &str, doc: &D) -> Result<Option<M>, ClientError>
+    where
+        M: JsonApiModel,
+        D: serde::Serialize,
+    {
+        let body = serde_json::to_vec(doc).map_err(errors::Error::from)?;
+        let response = self.send(method, &self.url(path, None), Some(body))?;
+        if response.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        let doc = document_data(&response)?;
+        Ok(Some(M::from_jsonapi_document(&doc)?))
+    }
+
+    /// Send a request, turning unsuccessful responses into errors
+    fn send(&self, method: Method, url: &str, body: Option<Vec<u8>>) -> Result<Response, ClientError> {
+        let mut headers = vec![("Accept".to_string(), JSONAPI_MEDIA_TYPE.to_string())];
+        if body.is_some() {
+            headers.push(("Content-Type".to_string(), JSONAPI_MEDIA_TYPE.to_string()));
+        }
+        headers.extend(self.headers.iter().cloned());
+
+        debug!("{} {}", method, url);
+        let response = self.transport.send(Request {
+            method,
+            url: url.to_string(),
+            headers,
+            body,
+        })?;
+
+        if response.is_success() {
+            return Ok(response);
+        }
+        match serde_json::from_slice::<DocumentError>(&response.body) {
+            Ok(errors) => Err(ClientError::Document {
+                status: response.status,
+                errors: Box::new(errors),
+            }),
+            Err(_) => Err(ClientError::Status {
+                status: response.status,
+                body: String::from_utf8_lossy(&response.body).into_owned(),
+            }),
+        }
+    }
+}
+
+fn document_data(response: &Response) -> Result<DocumentData, ClientError> {
+    let body = std::str::from_utf8(&response.body).map_err(|err| errors::Error::Parse {
+        pointer: None,
+        detail: err.to_string(),
+    })?;
+    match JsonApiDocument::from_str(body)? {
+        JsonApiDocument::Data(doc) => Ok(doc),
+        JsonApiDocument::Error(errors) => Err(ClientError::Document {
+            status: response.status,
+            errors: Box::new(errors),
+        }),
+    }
+}
+
+/// The primary data of a document as models
+fn models<M: JsonApiModel>(doc: &DocumentData) -> errors::Result<Vec<M>> {
+    let index = IncludedIndex::from_document(doc);
+    doc.primary_resources()
+        .iter()
+        .map(|resource| M::from_jsonapi_resource_with(resource, &index))
+        .collect()
+}
+
+/// Resolve a link from the document at `url` against that URL, as a
+/// browser would: links may be absolute, relative to the host, relative to
+/// the path or consist of a query only
+fn resolve_link(url: &str, link: &str) -> Result<String, ClientError> {
+    Url::parse(url)
+        .and_then(|url| url.join(link))
+        .map(String::from)
+        .map_err(|err| {
+            ClientError::Decode(errors::Error::Parse {
+                pointer: Some("/links/next".into()),
+                detail: format!("Invalid link {}: {}", link, err),
+            })
+        })
+}
+
+/// Iterator over the pages of a collection, see
+/// [`Client::pages`](struct.Client.html#method.pages)
+#[derive(Debug)]
+pub struct Pages<'a, T> {
+    client: &'a Client<T>,
+    next: Option<Result<String, ClientError>>,
+}
+
+impl<T: Transport> Iterator for Pages<'_, T> {
+    type Item = Result<DocumentData, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let url = match self.next.take()? {
+            Ok(url) => url,
+            Err(err) => return Some(Err(err)),
+        };
+        let page = self.client.fetch_url(&url);
+        if let Ok(ref doc) = page {
+            self.next = doc
+                .pagination()
+                .next
+                .map(|next| resolve_link(&url, &next))
+                .filter(|next| next.as_ref().map_or(true, |next| *next != url));
+        }
+        Some(page)
+    }
+}
+
+/// Iterator over the models of every page of a collection, see
+/// [`Client::stream`](struct.Client.html#method.stream)
+#[derive(Debug)]
+pub struct ModelStream<'a, T, M> {
+    pages: Pages<'a, T>,
+    models: vec::IntoIter<M>,
+}
+
+impl<T: Transport, M: JsonApiModel> Iterator for ModelStream<'_, T, M> {
+    type Item = Result<M, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(model) = self.models.next() {
+                return Some(Ok(model));
+            }
+            match self.pages.next()? {
+                Ok(doc) => match models(&doc) {
+                    Ok(models) => self.models = models.into_iter(),
+                    Err(err) => {
+                        self.pages.next = None;
+                        return Some(Err(err.into()));
+                    }
+                },
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// A [`Transport`](trait.Transport.html) answering requests with canned
+/// responses, for testing. Requests without a response fail with a
+/// `TransportError`. Every request is recorded.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: HashMap<(Method, String), Response>,
+    requests: RefCell<Vec<Request>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        MockTransport::default()
+    }
+
+    /// Respond to `method` requests for `url` with `status` and `body`
+    pub fn respond(self, method: Method, url: &str, status: u16, body: &str) -> Self {
+        self.respond_with(method, url, Response::new(status, body))
+    }
+
+    pub fn respond_with(mut self, method: Method, url: &str, response: Response) -> Self {
+        self.responses.insert((method, url.to_string()), response);
+        self
+    }
+
+    /// The requests sent so far
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.borrow().clone()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: Request) -> Result<Response, TransportError> {
+        let response = self
+            .responses
+            .get(&(request.method, request.url.clone()))
+            .cloned()
+            .ok_or_else(|| TransportError(format!("No response for {} {}", request.method, request.url)));
+        self.requests.borrow_mut().push(request);
+        response
+    }
+}
@@ -1,11 +1,140 @@
-error_chain!{
-    foreign_links {
-        SerdeJson(serde_json::Error);
+//! Defines the `Error` returned by fallible operations of this crate
+use std::error;
+use std::fmt;
+use std::io;
+use serde_json;
+use crate::api::{DiffPatchError, DocumentValidationError, LinkageValidationError, RequestValidationError};
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// Everything that can go wrong in this crate. Each variant carries the
+/// context needed to report the problem, see
+/// [`IntoJsonApiError`](../api_error/trait.IntoJsonApiError.html) for the
+/// conversion into a JSON:API error object.
+#[derive(Debug)]
+pub enum Error {
+    /// A document could not be parsed. `pointer` is a JSON Pointer to the
+    /// offending member, `None` if the input is not valid JSON.
+    Parse { pointer: Option<String>, detail: String },
+    /// A document violates the JSON:API specification
+    Document(Vec<DocumentValidationError>),
+    /// A request document is not acceptable for the endpoint it was sent to
+    Request(Vec<RequestValidationError>),
+    /// The resource linkage of a relationship document is invalid
+    Linkage(Vec<LinkageValidationError>),
+    /// A resource could not be converted into a model. `field` is the path to
+    /// the model field that failed to deserialize, if known.
+    Conversion {
+        resource_type: Option<String>,
+        id: Option<String>,
+        field: Option<String>,
+        detail: String,
+    },
+    /// A query parameter is invalid. `parameter` is `None` if the query string
+    /// as a whole could not be parsed.
+    Query { parameter: Option<String>, detail: String },
+    /// A patch could not be computed or applied
+    Patch(DiffPatchError),
+    /// A model could not be serialized
+    Json(serde_json::Error),
+    /// Reading or writing a document failed
+    Io(io::Error),
+}
+
+impl Error {
+    /// Attach the type and id of the resource being converted to a
+    /// `Conversion` error that lacks them
+    pub(crate) fn with_resource(self, resource_type: &str, id: &str) -> Self {
+        match self {
+            Error::Conversion { resource_type: None, id: None, field, detail } => Error::Conversion {
+                resource_type: Some(resource_type.to_string()),
+                id: Some(id.to_string()),
+                field,
+                detail,
+            },
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse { pointer: Some(ref pointer), ref detail } => {
+                write!(f, "Error parsing document at '{}': {}", pointer, detail)
+            }
+            Error::Parse { pointer: None, ref detail } => write!(f, "Error parsing document: {}", detail),
+            Error::Document(ref errors) => write!(f, "Invalid document: {:?}", errors),
+            Error::Request(ref errors) => write!(f, "Invalid request document: {:?}", errors),
+            Error::Linkage(ref errors) => write!(f, "Invalid resource linkage: {:?}", errors),
+            Error::Conversion { ref resource_type, ref id, ref field, ref detail } => {
+                write!(f, "Error converting resource")?;
+                if let (Some(resource_type), Some(id)) = (resource_type.as_ref(), id.as_ref()) {
+                    write!(f, " {}/{}", resource_type, id)?;
+                }
+                if let Some(ref field) = *field {
+                    write!(f, " at '{}'", field)?;
+                }
+                write!(f, ": {}", detail)
+            }
+            Error::Query { parameter: Some(ref parameter), ref detail } => {
+                write!(f, "Invalid query parameter '{}': {}", parameter, detail)
+            }
+            Error::Query { parameter: None, ref detail } => write!(f, "Invalid query string: {}", detail),
+            Error::Patch(ref error) => write!(f, "Error applying patch: {:?}", error),
+            Error::Json(ref error) => write!(f, "Error serializing model: {}", error),
+            Error::Io(ref error) => write!(f, "I/O error: {}", error),
+        }
     }
-    errors {
-        ResourceToModelError(t: String) {
-            description("Error converting Resource to Model")
-            display("Error converting Resource to Model: '{}'", t)
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Json(ref error) => Some(error),
+            Error::Io(ref error) => Some(error),
+            _ => None,
         }
     }
 }
+
+/// I/O failures of `serde_json` readers and writers are `Io` errors
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        if error.is_io() {
+            Error::Io(error.into())
+        } else {
+            Error::Json(error)
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<Vec<DocumentValidationError>> for Error {
+    fn from(errors: Vec<DocumentValidationError>) -> Self {
+        Error::Document(errors)
+    }
+}
+
+impl From<Vec<RequestValidationError>> for Error {
+    fn from(errors: Vec<RequestValidationError>) -> Self {
+        Error::Request(errors)
+    }
+}
+
+impl From<Vec<LinkageValidationError>> for Error {
+    fn from(errors: Vec<LinkageValidationError>) -> Self {
+        Error::Linkage(errors)
+    }
+}
+
+impl From<DiffPatchError> for Error {
+    fn from(error: DiffPatchError) -> Self {
+        Error::Patch(error)
+    }
+}
@@ -0,0 +1,302 @@
+//! Content hashes and entity tags for conditional requests.
+//!
+//! A [`ContentHash`](struct.ContentHash.html) is the SHA-256 digest of the
+//! [canonical serialization](../canonical/index.html) of a value, so equal
+//! documents hash the same regardless of member order. It is turned into an
+//! [`ETag`](struct.ETag.html) that `If-None-Match` and `If-Match` headers are
+//! evaluated against with [`if_none_match`](fn.if_none_match.html) and
+//! [`if_match`](fn.if_match.html).
+//!
+//! The tags returned by `Resource::etag` and `JsonApiDocument::etag` are
+//! weak, because the bytes actually served depend on how the value is
+//! serialized. Serve the body from [`canonical_body`](fn.canonical_body.html)
+//! for a strong tag.
+//!
+//! ```
+//! # extern crate jsonapi;
+//! use jsonapi::api::*;
+//! use jsonapi::etag::*;
+//! use std::str::FromStr;
+//!
+//! let doc = JsonApiDocument::from_str(r#"{ "data": { "type": "articles", "id": "1" } }"#).unwrap();
+//! let etag = doc.etag();
+//!
+//! // The client has the current version: respond with `304 Not Modified`
+//! assert!(!if_none_match(&etag.to_string(), Some(&etag)));
+//!
+//! // `If-Match` needs a strong tag, so serve the canonical body
+//! let (_body, strong) = canonical_body(&doc).unwrap();
+//! assert!(if_match(&strong.to_string(), Some(&strong)));
+//! assert!(!if_match(&etag.to_string(), Some(&strong)));
+//! // The client has an outdated version: respond with `412 Precondition Failed`
+//! assert!(!if_match(r#""outdated""#, Some(&strong)));
+//! ```
+use std::error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use crate::api::{JsonApiDocument, Resource};
+use crate::canonical::{to_canonical_vec, to_canonical_writer};
+use crate::errors::Result;
+
+/// SHA-256 digest of the canonical serialization of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentHash(pub [u8; 32]);
+
+impl ContentHash {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// A strong entity tag, for representations that are byte-for-byte
+    /// equal. Only use it for bodies serialized with
+    /// [`canonical`](../canonical/index.html), see
+    /// [`canonical_body`](fn.canonical_body.html).
+    pub fn strong_etag(&self) -> ETag {
+        ETag {
+            tag: self.to_string(),
+            weak: false,
+        }
+    }
+
+    /// A weak entity tag, for representations that are semantically
+    /// equivalent
+    pub fn weak_etag(&self) -> ETag {
+        ETag {
+            tag: self.to_string(),
+            weak: true,
+        }
+    }
+}
+
+/// Formats the digest as lowercase hexadecimal
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+struct HashWriter(Sha256);
+
+impl io::Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hash the canonical serialization of `value`
+pub fn content_hash<T: Serialize + ?Sized>(value: &T) -> Result<ContentHash> {
+    let mut writer = HashWriter(Sha256::new());
+    to_canonical_writer(&mut writer, value)?;
+    Ok(ContentHash(writer.0.finalize().into()))
+}
+
+/// Serialize `value` canonically, returning the body along with a strong
+/// entity tag for exactly those bytes
+pub fn canonical_body<T: Serialize + ?Sized>(value: &T) -> Result<(Vec<u8>, ETag)> {
+    let body = to_canonical_vec(value)?;
+    let mut hasher = Sha256::new();
+    hasher.update(&body);
+    let etag = ContentHash(hasher.finalize().into()).strong_etag();
+    Ok((body, etag))
+}
+
+impl Resource {
+    /// Hash of this resource, ignoring the order of its members
+    pub fn content_hash(&self) -> ContentHash {
+        content_hash(self).expect("resources serialize to JSON")
+    }
+
+    /// Weak entity tag based on the [`content_hash`](#method.content_hash)
+    pub fn etag(&self) -> ETag {
+        self.content_hash().weak_etag()
+    }
+}
+
+impl JsonApiDocument {
+    /// Hash of this document, ignoring the order of its members
+    pub fn content_hash(&self) -> ContentHash {
+        content_hash(self).expect("documents serialize to JSON")
+    }
+
+    /// Weak entity tag based on the [`content_hash`](#method.content_hash)
+    pub fn etag(&self) -> ETag {
+        self.content_hash().weak_etag()
+    }
+}
+
+/// An entity tag as used in the `ETag`, `If-Match` and `If-None-Match`
+/// headers. Formats as the header value, e.g. `W/"abc"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// Fails if `tag` contains characters that are not allowed in an entity
+    /// tag, such as `"`, spaces or control characters
+    pub fn strong<S: Into<String>>(tag: S) -> ::std::result::Result<Self, InvalidETag> {
+        Self::new(tag.into(), false)
+    }
+
+    /// Fails if `tag` contains characters that are not allowed in an entity
+    /// tag, such as `"`, spaces or control characters
+    pub fn weak<S: Into<String>>(tag: S) -> ::std::result::Result<Self, InvalidETag> {
+        Self::new(tag.into(), true)
+    }
+
+    fn new(tag: String, weak: bool) -> ::std::result::Result<Self, InvalidETag> {
+        if tag.bytes().all(is_etag_char) {
+            Ok(ETag { tag, weak })
+        } else {
+            Err(InvalidETag(tag))
+        }
+    }
+
+    /// The opaque tag, without quotes and weakness indicator
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Strong comparison: both tags are strong and equal
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison: the tags are equal, whether weak or not
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+impl FromStr for ETag {
+    type Err = InvalidETag;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        match parse_etag(s.trim()) {
+            Some((etag, "")) => Ok(etag),
+            _ => Err(InvalidETag(s.to_string())),
+        }
+    }
+}
+
+/// A header value that is not a valid entity tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidETag(pub String);
+
+impl fmt::Display for InvalidETag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid entity tag: {}", self.0)
+    }
+}
+
+impl error::Error for InvalidETag {}
+
+/// Evaluate an `If-None-Match` header against the entity tag of the
+/// `current` representation, `None` if there is none.
+///
+/// Returns `false` when the condition fails, i.e. a tag matches (weak
+/// comparison) or the header is `*` and a representation exists: respond
+/// with `304 Not Modified` to `GET` and `HEAD` requests and with
+/// `412 Precondition Failed` to others. A malformed header never fails.
+pub fn if_none_match(header: &str, current: Option<&ETag>) -> bool {
+    match (parse_list(header), current) {
+        (Some(Condition::Any), Some(_)) => false,
+        (Some(Condition::Tags(tags)), Some(current)) => !tags.iter().any(|tag| tag.weak_eq(current)),
+        _ => true,
+    }
+}
+
+/// Evaluate an `If-Match` header against the entity tag of the `current`
+/// representation, `None` if there is none.
+///
+/// Returns `false` when the condition fails, i.e. no tag matches (strong
+/// comparison) or the header is `*` and there is no representation: respond
+/// with `412 Precondition Failed`. A malformed header always fails.
+pub fn if_match(header: &str, current: Option<&ETag>) -> bool {
+    match (parse_list(header), current) {
+        (Some(Condition::Any), Some(_)) => true,
+        (Some(Condition::Tags(tags)), Some(current)) => tags.iter().any(|tag| tag.strong_eq(current)),
+        _ => false,
+    }
+}
+
+enum Condition {
+    Any,
+    Tags(Vec<ETag>),
+}
+
+fn parse_list(header: &str) -> Option<Condition> {
+    let mut rest = header.trim();
+    if rest == "*" {
+        return Some(Condition::Any);
+    }
+    let mut tags = Vec::new();
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ',']);
+        if rest.is_empty() {
+            break;
+        }
+        let (tag, next) = parse_etag(rest)?;
+        let next = next.trim_start_matches([' ', '\t']);
+        if !next.is_empty() && !next.starts_with(',') {
+            return None;
+        }
+        tags.push(tag);
+        rest = next;
+    }
+    match tags.len() {
+        0 => None,
+        _ => Some(Condition::Tags(tags)),
+    }
+}
+
+/// Parse an entity tag at the start of `s`, returning the rest
+fn parse_etag(s: &str) -> Option<(ETag, &str)> {
+    let (weak, s) = match s.strip_prefix("W/") {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let s = s.strip_prefix('"')?;
+    let end = s.find('"')?;
+    let tag = &s[..end];
+    if !tag.bytes().all(is_etag_char) {
+        return None;
+    }
+    Some((
+        ETag {
+            tag: tag.to_string(),
+            weak,
+        },
+        &s[end + 1..],
+    ))
+}
+
+/// `etagc` from RFC 9110: any visible character except `"`
+fn is_etag_char(byte: u8) -> bool {
+    byte == 0x21 || (0x23..=0x7e).contains(&byte) || byte >= 0x80
+}
@@ -0,0 +1,198 @@
+//! Defines the registry of JSON:API extensions a server or client
+//! understands. Every extension has a namespace, and the members it adds to
+//! a document are prefixed with it, e.g. `atomic:operations`.
+use std::collections::HashMap;
+use crate::api::*;
+use crate::media_type::{MediaType, Negotiator};
+
+/// Maps extension URIs to their namespaces
+///
+/// ```
+/// use jsonapi::api::*;
+/// use jsonapi::extension::*;
+///
+/// let registry = ExtensionRegistry::new()
+///     .register("https://jsonapi.org/ext/atomic", "atomic");
+///
+/// let doc: JsonApiValue = serde_json::from_str(r#"{
+///   "jsonapi": { "version": "1.1", "ext": ["https://jsonapi.org/ext/atomic"] },
+///   "atomic:operations": []
+/// }"#).unwrap();
+/// assert!(registry.validate_document(&doc).is_none());
+///
+/// let doc: JsonApiValue = serde_json::from_str(r#"{ "atomic:operations": [] }"#).unwrap();
+/// assert_eq!(
+///     registry.validate_document(&doc),
+///     Some(vec![ExtensionValidationError::UndeclaredExtension("/atomic:operations".into())])
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExtensionRegistry {
+    namespaces: HashMap<String, String>,
+}
+
+/// Enum to describe extension members a document is not allowed to contain
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtensionValidationError {
+    /// The member at the JSON Pointer has a namespace no registered
+    /// extension uses
+    UnknownNamespace(String),
+    /// The member at the JSON Pointer belongs to an extension the document
+    /// does not declare
+    UndeclaredExtension(String),
+    /// The extension URI is declared but not registered
+    UnsupportedExtension(String),
+}
+
+impl ExtensionRegistry {
+    pub fn new() -> Self {
+        ExtensionRegistry::default()
+    }
+
+    /// Register the extension identified by `uri`, whose members are
+    /// prefixed with `namespace`
+    pub fn register(mut self, uri: &str, namespace: &str) -> Self {
+        self.namespaces.insert(uri.to_string(), namespace.to_string());
+        self
+    }
+
+    /// The namespace of the extension identified by `uri`
+    pub fn namespace(&self, uri: &str) -> Option<&str> {
+        self.namespaces.get(uri).map(String::as_str)
+    }
+
+    /// The URI of the extension using `namespace`
+    pub fn uri(&self, namespace: &str) -> Option<&str> {
+        self.namespaces
+            .iter()
+            .find(|&(_, ns)| ns == namespace)
+            .map(|(uri, _)| uri.as_str())
+    }
+
+    /// A `Negotiator` that supports every registered extension
+    pub fn negotiator(&self) -> Negotiator {
+        self.namespaces
+            .keys()
+            .fold(Negotiator::new(), |negotiator, uri| negotiator.support_extension(uri))
+    }
+
+    /// Check that every namespaced member of `document` belongs to one of
+    /// the `declared` extensions, typically the `ext` parameter of the
+    /// request's media type, and that those extensions are registered. The
+    /// contents of `meta` objects and attribute values are not checked.
+    pub fn validate(&self, document: &JsonApiValue, declared: &[String]) -> Option<Vec<ExtensionValidationError>> {
+        let mut errors = Vec::<ExtensionValidationError>::new();
+
+        for uri in declared {
+            if self.namespace(uri).is_none() {
+                errors.push(ExtensionValidationError::UnsupportedExtension(uri.clone()));
+            }
+        }
+        let namespaces: Vec<&str> = declared.iter().filter_map(|uri| self.namespace(uri)).collect();
+        visit_members(document, "", &mut |name, pointer| {
+            self.check_member(name, pointer, &namespaces, &mut errors)
+        });
+
+        match errors.len() {
+            0 => None,
+            _ => Some(errors),
+        }
+    }
+
+    /// Like `validate`, with the extensions declared in the `jsonapi` member
+    /// of the document itself
+    pub fn validate_document(&self, document: &JsonApiValue) -> Option<Vec<ExtensionValidationError>> {
+        let declared: Vec<String> = document
+            .pointer("/jsonapi/ext")
+            .and_then(JsonApiValue::as_array)
+            .map(|ext| ext.iter().filter_map(|uri| uri.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+        self.validate(document, &declared)
+    }
+
+    /// The registered extensions applied to `document`: those it declares
+    /// in its `jsonapi` member and those whose members it contains
+    pub fn applied_extensions(&self, document: &JsonApiValue) -> Vec<String> {
+        let mut applied: Vec<String> = document
+            .pointer("/jsonapi/ext")
+            .and_then(JsonApiValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(JsonApiValue::as_str)
+            .filter(|uri| self.namespace(uri).is_some())
+            .map(String::from)
+            .collect();
+        visit_members(document, "", &mut |name, _| {
+            let uri = name.find(':').and_then(|idx| self.uri(&name[..idx]));
+            if let Some(uri) = uri.filter(|uri| !applied.iter().any(|ext| ext == uri)) {
+                applied.push(uri.to_string());
+            }
+        });
+        applied
+    }
+
+    /// The `Content-Type` of a response with `document`: the `negotiated`
+    /// media type with the extensions applied to the document in its `ext`
+    /// parameter
+    pub fn response_media_type(&self, negotiated: &MediaType, document: &JsonApiValue) -> MediaType {
+        let mut media_type = negotiated.clone();
+        for uri in self.applied_extensions(document) {
+            if !media_type.ext.contains(&uri) {
+                media_type.ext.push(uri);
+            }
+        }
+        media_type
+    }
+
+    fn check_member(&self, name: &str, pointer: &str, declared: &[&str], errors: &mut Vec<ExtensionValidationError>) {
+        if let Some(idx) = name.find(':') {
+            let namespace = &name[..idx];
+            if self.uri(namespace).is_none() {
+                errors.push(ExtensionValidationError::UnknownNamespace(pointer.to_string()));
+            } else if !declared.contains(&namespace) {
+                errors.push(ExtensionValidationError::UndeclaredExtension(pointer.to_string()));
+            }
+        }
+    }
+}
+
+/// Call `f` with the name and JSON Pointer of every member of the document
+/// `value`, skipping the contents of `meta` objects and attribute values
+fn visit_members<F: FnMut(&str, &str)>(value: &JsonApiValue, pointer: &str, f: &mut F) {
+    match *value {
+        JsonApiValue::Object(ref members) => {
+            for (name, member) in members {
+                let member_pointer = format!("{}/{}", pointer, escape_pointer_token(name));
+                f(name, &member_pointer);
+                match name.as_str() {
+                    "meta" => {}
+                    // Only the attribute names are members of the document
+                    "attributes" => {
+                        for attribute in member.as_object().into_iter().flat_map(|attrs| attrs.keys()) {
+                            f(attribute, &format!("{}/{}", member_pointer, escape_pointer_token(attribute)));
+                        }
+                    }
+                    _ => visit_members(member, &member_pointer, f),
+                }
+            }
+        }
+        JsonApiValue::Array(ref elements) => {
+            for (index, element) in elements.iter().enumerate() {
+                visit_members(element, &format!("{}/{}", pointer, index), f);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl JsonApiInfo {
+    /// Whether the document declares the extension identified by `uri`
+    pub fn uses_extension(&self, uri: &str) -> bool {
+        self.ext.as_ref().is_some_and(|ext| ext.iter().any(|e| e == uri))
+    }
+
+    /// Whether the document declares the profile identified by `uri`
+    pub fn uses_profile(&self, uri: &str) -> bool {
+        self.profile.as_ref().is_some_and(|profile| profile.iter().any(|p| p == uri))
+    }
+}
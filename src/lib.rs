@@ -63,6 +63,10 @@
 //! variable type in `Result`
 //!
 //! ```rust
+//! # extern crate jsonapi;
+//! # extern crate serde_json;
+//! use jsonapi::api::*;
+//!
 //! let serialized = r#"
 //! {
 //!   "data": [{
@@ -88,15 +92,18 @@
 //!     }
 //!   ]
 //! }"#;
-//! let data: Result<Resource, serde_json::Error> = serde_json::from_str(&serialized);
+//! let data: Result<JsonApiDocument, serde_json::Error> = serde_json::from_str(&serialized);
 //! assert_eq!(data.is_ok(), true);
 //! ```
 //!
 //! Or parse the `String` directly using the
-//! [Resource::from_str](api/struct.Resource.html) trait implementation
+//! [JsonApiDocument::from_str](api/enum.JsonApiDocument.html) trait implementation
 //!
 //! ```rust
-//! let data = Resource::from_str(&serialized);
+//! # use jsonapi::api::*;
+//! use std::str::FromStr;
+//! # let serialized = r#"{ "data": [{ "type": "articles", "id": "1" }] }"#;
+//! let data = JsonApiDocument::from_str(&serialized);
 //! assert_eq!(data.is_ok(), true);
 //! ```
 //!
@@ -128,6 +135,7 @@
 
 extern crate serde;
 extern crate serde_json;
+extern crate serde_path_to_error;
 #[macro_use]
 extern crate serde_derive;
 
@@ -136,11 +144,22 @@ extern crate queryst;
 #[macro_use]
 extern crate log;
 
-#[macro_use]
-extern crate error_chain;
 
 pub mod api;
+pub mod map;
+pub mod borrowed;
+pub mod api_error;
 pub mod array;
 pub mod query;
+pub mod media_type;
+pub mod extension;
+pub mod web;
+#[cfg(feature = "client")]
+pub mod client;
 pub mod model;
+pub mod patch;
+pub mod stream;
+pub mod canonical;
+#[cfg(feature = "etag")]
+pub mod etag;
 pub mod errors;
@@ -0,0 +1,342 @@
+//! The map used for links, meta, attributes and relationships.
+//!
+//! [`JsonApiMap`](struct.JsonApiMap.html) has the same API whether or not the
+//! `preserve_order` feature is enabled, so enabling it anywhere in a
+//! dependency graph doesn't break code elsewhere. By default members are
+//! sorted by name; with `preserve_order` they keep their insertion order.
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::Hash;
+use std::ops;
+
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::{btree_map as map_impl, BTreeMap as MapImpl};
+#[cfg(feature = "preserve_order")]
+use indexmap::{map as map_impl, IndexMap as MapImpl};
+
+/// Map of member names to values. Keys are `String`s unless the map borrows
+/// from its input, see [`borrowed`](../borrowed/index.html).
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent, bound(
+    serialize = "K: serde::Serialize, V: serde::Serialize",
+    deserialize = "K: serde::Deserialize<'de> + Ord + Hash, V: serde::Deserialize<'de>"
+))]
+pub struct JsonApiMap<V, K = String> {
+    map: MapImpl<K, V>,
+}
+
+impl<V, K: Ord + Hash> JsonApiMap<V, K> {
+    pub fn new() -> Self {
+        JsonApiMap { map: MapImpl::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get_mut(key)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.get_key_value(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Insert a member, returning the value it replaced. A replaced member
+    /// keeps its position.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    /// The member `key`, for in-place manipulation. A member inserted through
+    /// the entry is positioned as with [`insert`](#method.insert).
+    pub fn entry(&mut self, key: K) -> Entry<'_, V, K> {
+        Entry(self.map.entry(key))
+    }
+
+    /// Remove a member, leaving the order of the others unchanged
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove(key);
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove(key);
+    }
+
+    /// Remove a member and return it with its name, leaving the order of the
+    /// others unchanged
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord + Eq + Hash,
+    {
+        #[cfg(feature = "preserve_order")]
+        return self.map.shift_remove_entry(key);
+        #[cfg(not(feature = "preserve_order"))]
+        return self.map.remove_entry(key);
+    }
+
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.map.retain(keep);
+    }
+
+    pub fn iter(&self) -> Iter<'_, V, K> {
+        Iter(self.map.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, V, K> {
+        IterMut(self.map.iter_mut())
+    }
+
+    pub fn keys(&self) -> Keys<'_, V, K> {
+        Keys(self.map.keys())
+    }
+
+    pub fn into_keys(self) -> IntoKeys<V, K> {
+        IntoKeys(self.map.into_keys())
+    }
+
+    pub fn values(&self) -> Values<'_, V, K> {
+        Values(self.map.values())
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, V, K> {
+        ValuesMut(self.map.values_mut())
+    }
+
+    pub fn into_values(self) -> IntoValues<V, K> {
+        IntoValues(self.map.into_values())
+    }
+}
+
+impl<V, K> Default for JsonApiMap<V, K> {
+    fn default() -> Self {
+        JsonApiMap { map: MapImpl::new() }
+    }
+}
+
+impl<V: fmt::Debug, K: fmt::Debug> fmt::Debug for JsonApiMap<V, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+/// Maps are equal if they have the same members, in any order
+impl<V: PartialEq, K: Ord + Hash> PartialEq for JsonApiMap<V, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<V: Eq, K: Ord + Hash> Eq for JsonApiMap<V, K> {}
+
+/// Panics if the member is not present
+impl<Q, V, K> ops::Index<&Q> for JsonApiMap<V, K>
+where
+    K: Borrow<Q> + Ord + Hash,
+    Q: ?Sized + Ord + Eq + Hash,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+/// Panics if the member is not present
+impl<Q, V, K> ops::IndexMut<&Q> for JsonApiMap<V, K>
+where
+    K: Borrow<Q> + Ord + Hash,
+    Q: ?Sized + Ord + Eq + Hash,
+{
+    fn index_mut(&mut self, key: &Q) -> &mut V {
+        self.get_mut(key).expect("no entry found for key")
+    }
+}
+
+impl<V, K: Ord + Hash> FromIterator<(K, V)> for JsonApiMap<V, K> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        JsonApiMap {
+            map: MapImpl::from_iter(iter),
+        }
+    }
+}
+
+impl<V, K: Ord + Hash> Extend<(K, V)> for JsonApiMap<V, K> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.map.extend(iter);
+    }
+}
+
+impl<V, K: Ord + Hash, const N: usize> From<[(K, V); N]> for JsonApiMap<V, K> {
+    fn from(members: [(K, V); N]) -> Self {
+        members.into_iter().collect()
+    }
+}
+
+impl<V, K: Ord + Hash> From<std::collections::HashMap<K, V>> for JsonApiMap<V, K> {
+    fn from(members: std::collections::HashMap<K, V>) -> Self {
+        members.into_iter().collect()
+    }
+}
+
+/// A member of a `JsonApiMap` that may or may not be present, see
+/// [`JsonApiMap::entry`](struct.JsonApiMap.html#method.entry)
+pub struct Entry<'a, V, K = String>(map_impl::Entry<'a, K, V>);
+
+impl<'a, V: fmt::Debug, K: fmt::Debug + Ord + Hash> fmt::Debug for Entry<'a, V, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'a, V, K: Ord + Hash> Entry<'a, V, K> {
+    pub fn key(&self) -> &K {
+        self.0.key()
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.0.or_insert(default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        self.0.or_insert_with(default)
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.0.or_default()
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        Entry(self.0.and_modify(f))
+    }
+}
+
+macro_rules! delegate_iterator {
+    ($(#[$attr:meta])* $name:ident<$($lt:lifetime,)* V, K> => $inner:ty, $item:ty) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        pub struct $name<$($lt,)* V, K = String>($inner);
+
+        impl<$($lt,)* V, K> Iterator for $name<$($lt,)* V, K> {
+            type Item = $item;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next()
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl<$($lt,)* V, K> DoubleEndedIterator for $name<$($lt,)* V, K> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.0.next_back()
+            }
+        }
+
+        impl<$($lt,)* V, K> ExactSizeIterator for $name<$($lt,)* V, K> {}
+    };
+}
+
+delegate_iterator!(
+    /// Iterator over the members of a `JsonApiMap`
+    Iter<'a, V, K> => map_impl::Iter<'a, K, V>, (&'a K, &'a V)
+);
+delegate_iterator!(
+    /// Mutable iterator over the members of a `JsonApiMap`
+    IterMut<'a, V, K> => map_impl::IterMut<'a, K, V>, (&'a K, &'a mut V)
+);
+delegate_iterator!(
+    /// Owning iterator over the members of a `JsonApiMap`
+    IntoIter<V, K> => map_impl::IntoIter<K, V>, (K, V)
+);
+delegate_iterator!(
+    /// Iterator over the names of the members of a `JsonApiMap`
+    Keys<'a, V, K> => map_impl::Keys<'a, K, V>, &'a K
+);
+delegate_iterator!(
+    /// Owning iterator over the names of the members of a `JsonApiMap`
+    IntoKeys<V, K> => map_impl::IntoKeys<K, V>, K
+);
+delegate_iterator!(
+    /// Iterator over the values of a `JsonApiMap`
+    Values<'a, V, K> => map_impl::Values<'a, K, V>, &'a V
+);
+delegate_iterator!(
+    /// Mutable iterator over the values of a `JsonApiMap`
+    ValuesMut<'a, V, K> => map_impl::ValuesMut<'a, K, V>, &'a mut V
+);
+delegate_iterator!(
+    /// Owning iterator over the values of a `JsonApiMap`
+    IntoValues<V, K> => map_impl::IntoValues<K, V>, V
+);
+
+impl<V, K> IntoIterator for JsonApiMap<V, K> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.map.into_iter())
+    }
+}
+
+impl<'a, V, K> IntoIterator for &'a JsonApiMap<V, K> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter(self.map.iter())
+    }
+}
+
+impl<'a, V, K> IntoIterator for &'a mut JsonApiMap<V, K> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, V, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IterMut(self.map.iter_mut())
+    }
+}
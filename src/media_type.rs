@@ -0,0 +1,384 @@
+//! Defines the JSON:API media type and the content negotiation servers must
+//! perform on the `Content-Type` and `Accept` request headers
+use std::fmt;
+use std::str::FromStr;
+use crate::api::{ErrorSource, JsonApiError};
+use crate::api_error::HttpStatus;
+
+/// The JSON:API media type
+pub const JSONAPI_MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// A parsed media type. The JSON:API `ext` and `profile` parameters are
+/// split into their URIs, any other parameters are kept in `parameters`.
+///
+/// ```
+/// use jsonapi::media_type::*;
+/// use std::str::FromStr;
+///
+/// let media_type = MediaType::from_str(
+///     r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#
+/// ).unwrap();
+/// assert!(media_type.is_jsonapi());
+/// assert_eq!(media_type.ext, vec!["https://jsonapi.org/ext/atomic"]);
+/// assert_eq!(
+///     media_type.to_string(),
+///     r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaType {
+    pub type_: String,
+    pub subtype: String,
+    pub ext: Vec<String>,
+    pub profile: Vec<String>,
+    pub parameters: Vec<(String, String)>,
+}
+
+/// Enum to describe a media type that can't be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaTypeError {
+    Empty,
+    InvalidType(String),
+    InvalidParameter(String),
+}
+
+impl MediaType {
+    /// The JSON:API media type without parameters
+    pub fn jsonapi() -> Self {
+        MediaType {
+            type_: "application".into(),
+            subtype: "vnd.api+json".into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_jsonapi(&self) -> bool {
+        self.type_ == "application" && self.subtype == "vnd.api+json"
+    }
+
+    /// Whether this media range matches the JSON:API media type, i.e. is
+    /// the JSON:API media type itself, `application/*` or `*/*`
+    pub fn matches_jsonapi(&self) -> bool {
+        self.is_jsonapi()
+            || (self.type_ == "application" && self.subtype == "*")
+            || (self.type_ == "*" && self.subtype == "*")
+    }
+
+    /// The value of a parameter other than `ext` and `profile`
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parse a comma separated list of media types, such as the value of an
+    /// `Accept` header
+    pub fn parse_list(s: &str) -> Result<Vec<MediaType>, MediaTypeError> {
+        split_unquoted(s, ',')
+            .into_iter()
+            .filter(|part| !part.trim().is_empty())
+            .map(MediaType::from_str)
+            .collect()
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = MediaTypeError;
+
+    fn from_str(s: &str) -> Result<Self, MediaTypeError> {
+        let mut parts = split_unquoted(s, ';').into_iter();
+        let essence = parts.next().map(str::trim).unwrap_or("");
+        if essence.is_empty() {
+            return Err(MediaTypeError::Empty);
+        }
+
+        let (type_, subtype) = match essence.find('/') {
+            Some(idx) if idx > 0 && idx < essence.len() - 1 && !essence[idx + 1..].contains('/') => {
+                (&essence[..idx], &essence[idx + 1..])
+            }
+            _ => return Err(MediaTypeError::InvalidType(essence.to_string())),
+        };
+
+        let mut media_type = MediaType {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            ..Default::default()
+        };
+
+        for part in parts {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+            let (name, value) = match part.find('=') {
+                Some(idx) if idx > 0 => (part[..idx].trim().to_ascii_lowercase(), unquote(part[idx + 1..].trim())),
+                _ => return Err(MediaTypeError::InvalidParameter(part.to_string())),
+            };
+            match name.as_str() {
+                "ext" => media_type.ext.extend(value.split_whitespace().map(String::from)),
+                "profile" => media_type.profile.extend(value.split_whitespace().map(String::from)),
+                _ => media_type.parameters.push((name, value)),
+            }
+        }
+
+        Ok(media_type)
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        if !self.ext.is_empty() {
+            write!(f, "; ext=\"{}\"", self.ext.join(" "))?;
+        }
+        if !self.profile.is_empty() {
+            write!(f, "; profile=\"{}\"", self.profile.join(" "))?;
+        }
+        for (name, value) in &self.parameters {
+            if !value.is_empty() && value.chars().all(is_token_char) {
+                write!(f, "; {}={}", name, value)?;
+            } else {
+                write!(f, "; {}=\"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Enum to describe a request whose `Content-Type` or `Accept` header the
+/// server can't honour
+#[derive(Debug, Clone, PartialEq)]
+pub enum NegotiationError {
+    InvalidContentType(String),
+    UnsupportedMediaType(String),
+    UnsupportedParameter(String),
+    UnsupportedExtension(String),
+    InvalidAccept(String),
+    NotAcceptable,
+}
+
+/// Server-side content negotiation. Extensions are rejected unless they are
+/// declared supported, unsupported profiles are ignored.
+///
+/// ```
+/// use jsonapi::media_type::*;
+///
+/// let negotiator = Negotiator::new().support_extension("https://jsonapi.org/ext/atomic");
+///
+/// assert!(negotiator.check_content_type("application/vnd.api+json").is_ok());
+/// let error = negotiator.check_content_type("application/vnd.api+json; charset=utf-8").unwrap_err();
+/// assert_eq!(error, NegotiationError::UnsupportedParameter("charset".into()));
+/// assert_eq!(error.to_jsonapi_error().status, Some("415".into()));
+///
+/// let response = negotiator.negotiate(Some("application/vnd.api+json")).unwrap();
+/// assert_eq!(response.to_string(), "application/vnd.api+json");
+/// let error = negotiator.negotiate(Some("application/vnd.api+json; charset=utf-8")).unwrap_err();
+/// assert_eq!(error.status(), 406);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Negotiator {
+    extensions: Vec<String>,
+    profiles: Vec<String>,
+}
+
+impl Negotiator {
+    pub fn new() -> Self {
+        Negotiator::default()
+    }
+
+    /// Accept requests using the extension identified by `uri`
+    pub fn support_extension(mut self, uri: &str) -> Self {
+        self.extensions.push(uri.to_string());
+        self
+    }
+
+    /// Apply the profile identified by `uri` when a client requests it
+    pub fn support_profile(mut self, uri: &str) -> Self {
+        self.profiles.push(uri.to_string());
+        self
+    }
+
+    /// Check the `Content-Type` of a request with a body. Anything other
+    /// than the JSON:API media type, optionally with `ext` and `profile`
+    /// parameters, and extensions that are not supported are rejected with
+    /// a `415 Unsupported Media Type` error.
+    pub fn check_content_type(&self, content_type: &str) -> Result<MediaType, NegotiationError> {
+        let media_type = match MediaType::from_str(content_type) {
+            Ok(media_type) => media_type,
+            Err(_) => return Err(NegotiationError::InvalidContentType(content_type.to_string())),
+        };
+
+        if !media_type.is_jsonapi() {
+            return Err(NegotiationError::UnsupportedMediaType(media_type.to_string()));
+        }
+        if let Some((name, _)) = media_type.parameters.first() {
+            return Err(NegotiationError::UnsupportedParameter(name.clone()));
+        }
+        if let Some(ext) = media_type.ext.iter().find(|ext| !self.supports_extension(ext)) {
+            return Err(NegotiationError::UnsupportedExtension(ext.clone()));
+        }
+
+        Ok(media_type)
+    }
+
+    /// Choose the media type of the response from the `Accept` header of the
+    /// request, `None` if there is no such header. Instances of the JSON:API
+    /// media type with parameters other than `ext` and `profile`, or with
+    /// unsupported extensions, are ignored; if the header contains only such
+    /// instances the request is rejected with a `406 Not Acceptable` error.
+    ///
+    /// The returned media type carries the requested extensions and the
+    /// requested profiles that are supported, and belongs in the
+    /// `Content-Type` of the response.
+    pub fn negotiate(&self, accept: Option<&str>) -> Result<MediaType, NegotiationError> {
+        let accept = match accept {
+            Some(accept) if !accept.trim().is_empty() => accept,
+            _ => return Ok(MediaType::jsonapi()),
+        };
+        let ranges = match MediaType::parse_list(accept) {
+            Ok(ranges) => ranges,
+            Err(_) => return Err(NegotiationError::InvalidAccept(accept.to_string())),
+        };
+
+        let mut jsonapi_instances = 0;
+        let mut wildcard = false;
+        let acceptable = |range: &&MediaType| {
+            range
+                .parameter("q")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_none_or(|q| q > 0.0)
+        };
+        for range in ranges.iter().filter(acceptable) {
+            if !range.is_jsonapi() {
+                wildcard = wildcard || range.matches_jsonapi();
+                continue;
+            }
+            jsonapi_instances += 1;
+            let modified = range.parameters.iter().any(|(name, _)| name != "q");
+            if !modified && range.ext.iter().all(|ext| self.supports_extension(ext)) {
+                return Ok(MediaType {
+                    ext: range.ext.clone(),
+                    profile: range
+                        .profile
+                        .iter()
+                        .filter(|profile| self.profiles.contains(profile))
+                        .cloned()
+                        .collect(),
+                    ..MediaType::jsonapi()
+                });
+            }
+        }
+
+        if wildcard || jsonapi_instances == 0 {
+            // Either any media type is acceptable or the client did not ask
+            // for JSON:API at all, which is left to the application
+            Ok(MediaType::jsonapi())
+        } else {
+            Err(NegotiationError::NotAcceptable)
+        }
+    }
+
+    fn supports_extension(&self, uri: &str) -> bool {
+        self.extensions.iter().any(|ext| ext == uri)
+    }
+}
+
+impl NegotiationError {
+    /// The HTTP status code to respond with
+    pub fn status(&self) -> u16 {
+        match *self {
+            NegotiationError::InvalidAccept(_) | NegotiationError::NotAcceptable => 406,
+            _ => 415,
+        }
+    }
+
+    /// The error object describing this error, with the offending request
+    /// header as its source
+    pub fn to_jsonapi_error(&self) -> JsonApiError {
+        let (detail, header) = match *self {
+            NegotiationError::InvalidContentType(ref content_type) => (
+                format!("'{}' is not a valid media type", content_type),
+                "Content-Type",
+            ),
+            NegotiationError::UnsupportedMediaType(ref media_type) => (
+                format!("Expected {} but found '{}'", JSONAPI_MEDIA_TYPE, media_type),
+                "Content-Type",
+            ),
+            NegotiationError::UnsupportedParameter(ref name) => (
+                format!("The media type parameter '{}' is not allowed", name),
+                "Content-Type",
+            ),
+            NegotiationError::UnsupportedExtension(ref ext) => (
+                format!("The extension '{}' is not supported", ext),
+                "Content-Type",
+            ),
+            NegotiationError::InvalidAccept(ref accept) => (
+                format!("'{}' is not a valid list of media types", accept),
+                "Accept",
+            ),
+            NegotiationError::NotAcceptable => (
+                format!(
+                    "None of the instances of {} in the Accept header can be served",
+                    JSONAPI_MEDIA_TYPE
+                ),
+                "Accept",
+            ),
+        };
+
+        JsonApiError {
+            status: Some(self.status().to_string()),
+            title: HttpStatus::new(self.status())
+                .and_then(HttpStatus::canonical_reason)
+                .map(str::to_string),
+            detail: Some(detail),
+            source: Some(ErrorSource {
+                header: Some(header.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+/// Split `s` on `separator`, ignoring separators inside quoted strings
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            parts.push(&s[start..idx]);
+            start = idx + c.len_utf8();
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        let mut unquoted = String::new();
+        let mut chars = value[1..value.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => unquoted.extend(chars.next()),
+                c => unquoted.push(c),
+            }
+        }
+        unquoted
+    } else {
+        value.to_string()
+    }
+}
@@ -5,9 +5,10 @@
 //! [`Resource`](../api/struct.Resource.html)
 pub use std::collections::HashMap;
 pub use crate::api::*;
+use std::borrow::Cow;
 use crate::errors::*;
 use serde::{Deserialize, Serialize};
-use serde_json::{from_value, to_value, Value, Map};
+use serde_json::{to_value, Value, Map};
 
 /// A trait for any struct that can be converted from/into a
 /// [`Resource`](api/struct.Resource.tml). The only requirement is that your
@@ -35,6 +36,56 @@ where
 
         let visited_relationships: Vec<&str> = Vec::new();
         Self::from_serializable(Self::resource_to_attrs(resource, included, &visited_relationships))
+            .map_err(|err| err.with_resource(&resource._type, &resource.id))
+    }
+
+    /// Create an instance of the calling struct from a single `resource`,
+    /// looking up the resources its relationships refer to with `resolver`.
+    /// A resolver can be a slice of resources (such as `included`), an
+    /// [`IncludedIndex`](../api/struct.IncludedIndex.html) or a closure, e.g.
+    /// one backed by a cache.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate serde_derive;
+    /// #[macro_use] extern crate jsonapi;
+    /// use jsonapi::api::*;
+    /// use jsonapi::model::*;
+    /// use std::str::FromStr;
+    ///
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    /// struct Dog {
+    ///     id: String,
+    ///     name: String,
+    ///     owner: Person,
+    /// }
+    /// jsonapi_model!(Dog; "dogs"; has one owner);
+    ///
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    /// struct Person {
+    ///     id: String,
+    ///     name: String,
+    /// }
+    /// jsonapi_model!(Person; "people");
+    ///
+    /// let resource = Resource::from_str(r#"{
+    ///   "type": "dogs", "id": "1",
+    ///   "attributes": { "name": "Rex" },
+    ///   "relationships": { "owner": { "data": { "type": "people", "id": "9" } } }
+    /// }"#).unwrap();
+    ///
+    /// let cache = |identifier: &ResourceIdentifier| {
+    ///     Some(Person { id: identifier.id.clone(), name: "Dan".into() }.to_jsonapi_resource().0)
+    /// };
+    ///
+    /// let dog = Dog::from_jsonapi_resource_with(&resource, &cache).unwrap();
+    /// assert_eq!(dog.owner.name, "Dan");
+    /// ```
+    fn from_jsonapi_resource_with<R>(resource: &Resource, resolver: &R) -> Result<Self>
+        where R: ResourceResolver + ?Sized
+    {
+        let visited_relationships: Vec<&str> = Vec::new();
+        Self::from_serializable(Self::resource_to_attrs_with(resource, resolver, &visited_relationships))
+            .map_err(|err| err.with_resource(&resource._type, &resource.id))
     }
 
     /// Create a single resource object or collection of resource
@@ -46,7 +97,7 @@ where
         match doc.data.as_ref() {
             Some(primary_data) => {
                 match *primary_data {
-                    PrimaryData::None => bail!("Document had no data"),
+                    PrimaryData::None => Err(no_data_error()),
                     PrimaryData::Single(ref resource) => {
                         Self::from_jsonapi_resource(resource, &doc.included)
                     }
@@ -60,15 +111,128 @@ where
                     }
                 }
             }
-            None => bail!("Document had no data"),
+            None => Err(no_data_error()),
         }
     }
 
+    /// Applies the `attributes` and `relationships` present in `resource`
+    /// (typically the `data` of a `PATCH` request) onto this instance, leaving
+    /// every other field untouched. Relationship linkage is resolved against
+    /// the related models this instance already has, then against
+    /// `included`, as update requests usually carry no `included` resources.
+    /// A relationship that can't be resolved is an error.
+    ///
+    /// The `type` and `id` of `resource` must match those of this instance.
+    /// If an error is returned the instance is left unchanged.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate serde_derive;
+    /// #[macro_use] extern crate jsonapi;
+    /// use jsonapi::api::*;
+    /// use jsonapi::model::*;
+    /// use std::str::FromStr;
+    ///
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    /// struct Flea {
+    ///     id: String,
+    ///     name: String,
+    ///     legs: u32,
+    /// }
+    ///
+    /// jsonapi_model!(Flea; "flea");
+    ///
+    /// let mut flea = Flea {
+    ///     id: "2".into(),
+    ///     name: "rick".into(),
+    ///     legs: 6,
+    /// };
+    ///
+    /// let patch = Resource::from_str(r#"{
+    ///   "type": "flea",
+    ///   "id": "2",
+    ///   "attributes": { "name": "morty" }
+    /// }"#).unwrap();
+    ///
+    /// flea.update_from_jsonapi_resource(&patch, &None).unwrap();
+    /// assert_eq!(flea.name, "morty");
+    /// assert_eq!(flea.legs, 6);
+    /// ```
+    fn update_from_jsonapi_resource(&mut self, resource: &Resource, included: &Option<Resources>)
+        -> Result<()>
+    {
+        if resource._type != self.jsonapi_type() || resource.id != self.jsonapi_id() {
+            return Err(Error::Conversion {
+                resource_type: Some(resource._type.clone()),
+                id: Some(resource.id.clone()),
+                field: None,
+                detail: format!("does not match model {}/{}", self.jsonapi_type(), self.jsonapi_id()),
+            });
+        }
+
+        let mut attrs = match to_value(&*self)? {
+            Value::Object(attrs) => attrs,
+            _ => return Err(Error::Conversion {
+                resource_type: Some(self.jsonapi_type()),
+                id: Some(self.jsonapi_id()),
+                field: None,
+                detail: "model is not a Value::Object".to_string(),
+            }),
+        };
+
+        for (name, value) in &resource.attributes {
+            attrs.insert(name.clone(), value.clone());
+        }
+
+        if let Some(relations) = resource.relationships.as_ref() {
+            let mut known = self.build_included().unwrap_or_default();
+            known.extend(included.iter().flatten().cloned());
+            let known = Some(known);
+
+            let visited_relationships: Vec<&str> = Vec::new();
+            let resolve = |name: &str, identifier: &ResourceIdentifier| {
+                known.as_deref()
+                    .and_then(|known| Self::lookup(identifier, known))
+                    .map(|r| Self::resource_to_attrs(r, &known, &visited_relationships))
+                    .ok_or_else(|| Error::Conversion {
+                        resource_type: Some(resource._type.clone()),
+                        id: Some(resource.id.clone()),
+                        field: Some(name.to_string()),
+                        detail: format!(
+                            "refers to {}/{} which is neither related nor included",
+                            identifier._type, identifier.id
+                        ),
+                    })
+            };
+
+            for (name, relation) in relations {
+                let value = match relation.data {
+                    // Only links or meta were sent, the linkage is unchanged
+                    None => continue,
+                    Some(IdentifierData::None) => Value::Null,
+                    Some(IdentifierData::Single(ref identifier)) => {
+                        to_value(resolve(name, identifier)?)?
+                    },
+                    Some(IdentifierData::Multiple(ref identifiers)) => {
+                        let found = identifiers
+                            .iter()
+                            .map(|identifier| resolve(name, identifier))
+                            .collect::<Result<Vec<ResourceAttributes>>>()?;
+                        to_value(found)?
+                    },
+                };
+                attrs.insert(name.clone(), value);
+            }
+        }
+
+        *self = Self::from_serializable(attrs)
+            .map_err(|err| err.with_resource(&resource._type, &resource.id))?;
+        Ok(())
+    }
+
     /// Converts the instance of the struct into a
     /// [`Resource`](../api/struct.Resource.html)
     fn to_jsonapi_resource(&self) -> (Resource, Option<Resources>) {
-        if let Value::Object(mut attrs) = to_value(self).unwrap() {
-            let _ = attrs.remove("id");
+        if let Value::Object(attrs) = to_value(self).unwrap() {
             let resource = Resource {
                 _type: self.jsonapi_type(),
                 id: self.jsonapi_id(),
@@ -79,7 +243,7 @@ where
 
             (resource, self.build_included())
         } else {
-            panic!(format!("{} is not a Value::Object", self.jsonapi_type()))
+            panic!("{} is not a Value::Object", self.jsonapi_type())
         }
     }
 
@@ -98,6 +262,19 @@ where
     }
 
 
+    /// Builds the
+    /// [`RelationshipDocument`](../api/struct.RelationshipDocument.html)
+    /// served by the relationship endpoint for `name`, or `None` if the model
+    /// has no such relationship
+    fn to_jsonapi_relationship_document(&self, name: &str) -> Option<RelationshipDocument> {
+        self.build_relationships()
+            .and_then(|relationships| {
+                relationships
+                    .get(name)
+                    .and_then(RelationshipDocument::from_relationship)
+            })
+    }
+
     #[doc(hidden)]
     fn build_has_one<M: JsonApiModel>(model: &M) -> Relationship {
         Relationship {
@@ -124,15 +301,19 @@ where
         }
     }
 
-    /* Attribute corresponding to the model is removed from the Map
-     * before calling this, so there's no need to ignore it like we do
-     * with the attributes that correspond with relationships.
+    /* The `id` of the model and the fields that correspond with
+     * relationships are not attributes, so they are left out. `id` is
+     * skipped rather than removed from the Map to keep the order of the
+     * remaining members.
      * */
     #[doc(hidden)]
     fn extract_attributes(attrs: &Map<String, Value>) -> ResourceAttributes {
         attrs
             .iter()
             .filter(|&(key, _)| {
+                if key == "id" {
+                    return false;
+                }
                 if let Some(fields) = Self::relationship_fields() {
                     if fields.contains(&key.as_str()) {
                         return false;
@@ -162,12 +343,9 @@ where
     fn lookup<'a>(needle: &ResourceIdentifier, haystack: &'a [Resource])
         -> Option<&'a Resource>
     {
-        for resource in haystack {
-            if resource._type == needle._type && resource.id == needle.id {
-                return Some(resource);
-            }
-        }
-        None
+        haystack
+            .iter()
+            .find(|resource| resource._type == needle._type && resource.id == needle.id)
     }
 
     /// Return a [`ResourceAttributes`](../api/struct.ResourceAttributes.html)
@@ -192,7 +370,26 @@ where
     fn resource_to_attrs(resource: &Resource, included: &Option<Resources>, visited_relationships: &Vec<&str>)
         -> ResourceAttributes
     {
-        let mut new_attrs = HashMap::new();
+        match included.as_ref() {
+            Some(inc) => Self::resource_to_attrs_with(resource, inc.as_slice(), visited_relationships),
+            None => {
+                let mut new_attrs = ResourceAttributes::new();
+                new_attrs.clone_from(&resource.attributes);
+                new_attrs.insert("id".into(), resource.id.clone().into());
+                new_attrs
+            }
+        }
+    }
+
+    /// Same as [`resource_to_attrs`](#method.resource_to_attrs), but the
+    /// resources for relationships are looked up with a
+    /// [`ResourceResolver`](trait.ResourceResolver.html)
+    #[doc(hidden)]
+    fn resource_to_attrs_with<R>(resource: &Resource, resolver: &R, visited_relationships: &Vec<&str>)
+        -> ResourceAttributes
+        where R: ResourceResolver + ?Sized
+    {
+        let mut new_attrs = ResourceAttributes::new();
         new_attrs.clone_from(&resource.attributes);
         new_attrs.insert("id".into(), resource.id.clone().into());
 
@@ -205,38 +402,36 @@ where
         }
 
         if let Some(relations) = resource.relationships.as_ref() {
-            if let Some(inc) = included.as_ref() {
-                for (name, relation) in relations {
-                    // If we have already visited this resource object, exit early and do not
-                    // recurse through the relations
-                    if this_visited.contains(&name.as_str()) {
-                        return new_attrs;
-                    }
-                    // Track that we have visited this relationship to avoid infinite recursion
-                    this_visited.push(name);
-
-                    let value = match relation.data {
-                        Some(IdentifierData::None) => Value::Null,
-                        Some(IdentifierData::Single(ref identifier)) => {
-                            let found = Self::lookup(identifier, inc)
-                                .map(|r| Self::resource_to_attrs(r, included, &this_visited) );
-                            to_value(found)
-                                .expect("Casting Single relation to value")
-                        },
-                        Some(IdentifierData::Multiple(ref identifiers)) => {
-                            let found: Vec<Option<ResourceAttributes>> =
-                                identifiers.iter().map(|identifier|{
-                                    Self::lookup(identifier, inc).map(|r|{
-                                        Self::resource_to_attrs(r, included, &this_visited)
-                                    })
-                                }).collect();
-                            to_value(found)
-                                .expect("Casting Multiple relation to value")
-                        },
-                        None => Value::Null,
-                    };
-                    new_attrs.insert(name.to_string(), value);
+            for (name, relation) in relations {
+                // If we have already visited this resource object, exit early and do not
+                // recurse through the relations
+                if this_visited.contains(&name.as_str()) {
+                    return new_attrs;
                 }
+                // Track that we have visited this relationship to avoid infinite recursion
+                this_visited.push(name);
+
+                let value = match relation.data {
+                    Some(IdentifierData::None) => Value::Null,
+                    Some(IdentifierData::Single(ref identifier)) => {
+                        let found = resolver.resolve(identifier)
+                            .map(|r| Self::resource_to_attrs_with(&r, resolver, &this_visited) );
+                        to_value(found)
+                            .expect("Casting Single relation to value")
+                    },
+                    Some(IdentifierData::Multiple(ref identifiers)) => {
+                        let found: Vec<Option<ResourceAttributes>> =
+                            identifiers.iter().map(|identifier|{
+                                resolver.resolve(identifier).map(|r|{
+                                    Self::resource_to_attrs_with(&r, resolver, &this_visited)
+                                })
+                            }).collect();
+                        to_value(found)
+                            .expect("Casting Multiple relation to value")
+                    },
+                    None => Value::Null,
+                };
+                new_attrs.insert(name.to_string(), value);
             }
         }
         new_attrs
@@ -244,7 +439,75 @@ where
 
     #[doc(hidden)]
     fn from_serializable<S: Serialize>(s: S) -> Result<Self> {
-        from_value(to_value(s)?).map_err(Error::from)
+        serde_path_to_error::deserialize(to_value(s)?).map_err(|err| {
+            // An empty path is displayed as "."
+            let field = match err.path().to_string() {
+                ref path if path == "." => None,
+                path => Some(path),
+            };
+            Error::Conversion {
+                resource_type: None,
+                id: None,
+                field,
+                detail: err.into_inner().to_string(),
+            }
+        })
+    }
+}
+
+fn no_data_error() -> Error {
+    Error::Conversion {
+        resource_type: None,
+        id: None,
+        field: None,
+        detail: "Document had no data".to_string(),
+    }
+}
+
+/// Looks up the [`Resource`](../api/struct.Resource.html) a
+/// [`ResourceIdentifier`](../api/struct.ResourceIdentifier.html) refers to
+/// when converting a resource into a model. Resolvers that hold the
+/// resources lend them with `Cow::Borrowed`, closures return owned ones.
+pub trait ResourceResolver {
+    fn resolve(&self, identifier: &ResourceIdentifier) -> Option<Cow<'_, Resource>>;
+}
+
+impl<F> ResourceResolver for F
+where
+    F: Fn(&ResourceIdentifier) -> Option<Resource>,
+{
+    fn resolve(&self, identifier: &ResourceIdentifier) -> Option<Cow<'_, Resource>> {
+        self(identifier).map(Cow::Owned)
+    }
+}
+
+impl ResourceResolver for [Resource] {
+    fn resolve(&self, identifier: &ResourceIdentifier) -> Option<Cow<'_, Resource>> {
+        self.iter()
+            .find(|resource| resource._type == identifier._type && resource.id == identifier.id)
+            .map(Cow::Borrowed)
+    }
+}
+
+impl ResourceResolver for IncludedIndex {
+    fn resolve(&self, identifier: &ResourceIdentifier) -> Option<Cow<'_, Resource>> {
+        self.get(identifier).map(Cow::Borrowed)
+    }
+}
+
+impl Resource {
+    /// Convert into a model without resolving any relationships. See
+    /// [`JsonApiModel::from_jsonapi_resource`](trait.JsonApiModel.html#method.from_jsonapi_resource)
+    pub fn into_model<T: JsonApiModel>(self) -> Result<T> {
+        T::from_jsonapi_resource(&self, &None)
+    }
+
+    /// Convert into a model, resolving relationships with `resolver`. See
+    /// [`JsonApiModel::from_jsonapi_resource_with`](trait.JsonApiModel.html#method.from_jsonapi_resource_with)
+    pub fn into_model_with<T, R>(self, resolver: &R) -> Result<T>
+        where T: JsonApiModel, R: ResourceResolver + ?Sized
+    {
+        T::from_jsonapi_resource_with(&self, resolver)
     }
 }
 
@@ -339,8 +602,38 @@ impl<M: JsonApiModel> JsonApiModel for Box<M> {
 /// When applied this macro implements the
 /// [`JsonApiModel`](model/trait.JsonApiModel.html) trait for the provided type
 ///
+/// Models without relationships can opt into an implementation of
+/// `TryFrom<Resource>` with `try_from`. It is not available to models with
+/// relationships, as a `Resource` alone has no `included` resources to
+/// resolve them against.
+///
+/// ```
+/// #[macro_use] extern crate serde_derive;
+/// #[macro_use] extern crate jsonapi;
+/// use jsonapi::model::*;
+/// use std::convert::TryFrom;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Flea {
+///     id: String,
+///     name: String,
+/// }
+/// jsonapi_model!(Flea; "flea"; try_from);
+///
+/// let (resource, _) = Flea { id: "1".into(), name: "rick".into() }.to_jsonapi_resource();
+/// assert_eq!(Flea::try_from(resource).unwrap().name, "rick");
+/// ```
 #[macro_export]
 macro_rules! jsonapi_model {
+    (@try_from $model:ty) => (
+        impl ::std::convert::TryFrom<$crate::api::Resource> for $model {
+            type Error = $crate::errors::Error;
+
+            fn try_from(resource: $crate::api::Resource) -> $crate::errors::Result<Self> {
+                <$model as $crate::model::JsonApiModel>::from_jsonapi_resource(&resource, &None)
+            }
+        }
+    );
     ($model:ty; $type:expr) => (
         impl JsonApiModel for $model {
             fn jsonapi_type(&self) -> String { $type.to_string() }
@@ -350,6 +643,10 @@ macro_rules! jsonapi_model {
             fn build_included(&self) -> Option<Resources> { None }
         }
     );
+    ($model:ty; $type:expr; try_from) => (
+        jsonapi_model!($model; $type);
+        jsonapi_model!(@try_from $model);
+    );
     ($model:ty; $type:expr;
         has one $( $has_one:ident ),*
     ) => (
@@ -378,7 +675,7 @@ macro_rules! jsonapi_model {
             }
 
             fn build_relationships(&self) -> Option<Relationships> {
-                let mut relationships = HashMap::new();
+                let mut relationships = Relationships::new();
                 $(
                     relationships.insert(stringify!($has_one).into(),
                         Self::build_has_one(&self.$has_one)
@@ -0,0 +1,392 @@
+//! Conversions between a [`PatchSet`](../api/struct.PatchSet.html) and other
+//! representations of a change to a resource: an
+//! [RFC 6902](https://tools.ietf.org/html/rfc6902) JSON Patch against the
+//! resource JSON, and a minimal JSON:API update document
+use serde_json;
+use crate::api::*;
+
+/// A JSON Patch document, a sequence of operations
+pub type JsonPatch = Vec<JsonPatchOperation>;
+
+/// A single RFC 6902 JSON Patch operation. `path` and `from` are JSON
+/// Pointers into the serialized `Resource`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOperation {
+    Add { path: String, value: JsonApiValue },
+    Remove { path: String },
+    Replace { path: String, value: JsonApiValue },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JsonApiValue },
+}
+
+impl PatchSet {
+    /// Convert into a JSON Patch that turns the serialized form of `resource`
+    /// into the serialized form of the patched resource.
+    ///
+    /// Every `remove` and `replace` is preceded by a `test` of the value it
+    /// expects to find, so the JSON Patch keeps the conflict detection of
+    /// [`Resource::apply`](../api/struct.Resource.html#method.apply).
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use jsonapi::patch::*;
+    /// use std::str::FromStr;
+    ///
+    /// let before = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1", "attributes": { "likes": 250 }
+    /// }"#).unwrap();
+    /// let after = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1", "attributes": { "likes": 251 }
+    /// }"#).unwrap();
+    ///
+    /// let json_patch = before.diff(after).unwrap().to_json_patch(&before).unwrap();
+    /// assert_eq!(
+    ///     serde_json::to_string(&json_patch).unwrap(),
+    ///     r#"[{"op":"test","path":"/attributes/likes","value":250},{"op":"replace","path":"/attributes/likes","value":251}]"#
+    /// );
+    /// ```
+    pub fn to_json_patch(&self, resource: &Resource) -> Result<JsonPatch, DiffPatchError> {
+        let mut working = resource.clone();
+        let mut operations = JsonPatch::new();
+
+        for patch in &self.patches {
+            let (parent, parent_exists) = parent_member(&working, patch.patch_type);
+            if !parent_exists && patch.operation != PatchOperation::Remove {
+                operations.push(JsonPatchOperation::Add {
+                    path: parent.to_string(),
+                    value: JsonApiValue::Object(serde_json::Map::new()),
+                });
+            }
+
+            let mut path = match patch.patch_type {
+                // Nested changes from `Resource::diff_deep` are already
+                // pointers into the attributes
+                PatchType::Attribute if patch.subject.starts_with('/') => {
+                    format!("{}{}", parent, patch.subject)
+                }
+                _ => format!("{}/{}", parent, escape_pointer_token(&patch.subject)),
+            };
+            if patch.patch_type == PatchType::RelationshipMember {
+                path.push_str("/data/");
+                match (patch.operation, patch.index) {
+                    (PatchOperation::Remove, _) => {
+                        let index = member_index(&working, patch)?;
+                        path.push_str(&index.to_string());
+                    }
+                    (_, Some(index)) => path.push_str(&index.to_string()),
+                    (_, None) => path.push('-'),
+                }
+            }
+
+            match patch.operation {
+                PatchOperation::Add => operations.push(JsonPatchOperation::Add {
+                    path,
+                    value: patch.next.clone(),
+                }),
+                PatchOperation::Remove => {
+                    operations.push(JsonPatchOperation::Test {
+                        path: path.clone(),
+                        value: patch.previous.clone(),
+                    });
+                    operations.push(JsonPatchOperation::Remove { path });
+                }
+                PatchOperation::Replace => {
+                    operations.push(JsonPatchOperation::Test {
+                        path: path.clone(),
+                        value: patch.previous.clone(),
+                    });
+                    operations.push(JsonPatchOperation::Replace {
+                        path,
+                        value: patch.next.clone(),
+                    });
+                }
+            }
+
+            working.apply(&PatchSet {
+                resource_type: self.resource_type.clone(),
+                resource_id: self.resource_id.clone(),
+                patches: vec![patch.clone()],
+            })?;
+
+            // `Resource::apply` drops a map once its last member is removed
+            if parent_exists && !parent_member(&working, patch.patch_type).1 {
+                operations.push(JsonPatchOperation::Remove {
+                    path: parent.to_string(),
+                });
+            }
+        }
+
+        Ok(operations)
+    }
+
+    /// Build a `PatchSet` from a JSON Patch against the serialized form of
+    /// `resource`. Each operation is applied in turn and the resulting change
+    /// is described with the same patches [`Resource::diff`](../api/struct.Resource.html#method.diff)
+    /// would produce. A failing `test` operation is reported as
+    /// `DiffPatchError::IncorrectPropertyValue` and a path that doesn't exist
+    /// as `DiffPatchError::NonExistentProperty`.
+    pub fn from_json_patch(
+        resource: &Resource,
+        json_patch: &[JsonPatchOperation],
+    ) -> Result<PatchSet, DiffPatchError> {
+        let mut working = resource.clone();
+        let mut patchset = PatchSet::new_for(resource);
+
+        for operation in json_patch {
+            let mut value = resource_to_value(&working);
+            apply_json_patch_operation(&mut value, operation)?;
+
+            let next: Resource = serde_json::from_value(value)
+                .map_err(|_| DiffPatchError::IncorrectPropertyValue(operation.path().to_string()))?;
+            if next.id != working.id {
+                return Err(DiffPatchError::IncorrectPropertyValue("id".into()));
+            }
+
+            let mut step = working.diff(next.clone())?;
+            patchset.patches.append(&mut step.patches);
+            working = next;
+        }
+
+        Ok(patchset)
+    }
+
+    /// Build the minimal JSON:API `PATCH` request document that updates
+    /// `resource` as described by this `PatchSet`. Only attributes and
+    /// relationships that changed are included. Removed attributes are sent
+    /// as `null` and changed relationships are sent with their full linkage,
+    /// as the specification requires a relationship to be replaced as a
+    /// whole. Changes to links and meta are not part of an update request and
+    /// are left out.
+    ///
+    /// ```
+    /// use jsonapi::api::*;
+    /// use std::str::FromStr;
+    ///
+    /// let before = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1", "attributes": { "title": "Rails", "likes": 250 }
+    /// }"#).unwrap();
+    /// let after = Resource::from_str(r#"{
+    ///   "type": "posts", "id": "1", "attributes": { "title": "Rails", "likes": 251 }
+    /// }"#).unwrap();
+    ///
+    /// let doc = before.diff(after).unwrap().to_update_document(&before).unwrap();
+    /// assert_eq!(
+    ///     serde_json::to_string(&doc).unwrap(),
+    ///     r#"{"data":{"type":"posts","id":"1","attributes":{"likes":251}}}"#
+    /// );
+    /// ```
+    pub fn to_update_document(
+        &self,
+        resource: &Resource,
+    ) -> Result<ResourceUpdateDocument, DiffPatchError> {
+        let patched = resource.patch(self)?;
+        let mut update = ResourceUpdate {
+            _type: patched._type.clone(),
+            id: patched.id.clone(),
+            ..Default::default()
+        };
+
+        for patch in &self.patches {
+            match patch.patch_type {
+                PatchType::Attribute => {
+                    // Nested changes from `Resource::diff_deep` send the
+                    // whole top-level attribute
+                    let name = match split_attribute_pointer(&patch.subject) {
+                        Some((name, _)) => name,
+                        None => patch.subject.clone(),
+                    };
+                    let value = patched
+                        .attributes
+                        .get(&name)
+                        .cloned()
+                        .unwrap_or(JsonApiValue::Null);
+                    update.attributes.insert(name, value);
+                }
+                PatchType::Relationship | PatchType::RelationshipMember => {
+                    let previous = resource.get_relationship(&patch.subject).and_then(|r| r.data.as_ref());
+                    let next = patched.get_relationship(&patch.subject).and_then(|r| r.data.clone());
+                    // A removed relationship is cleared, with `[]` for
+                    // to-many and `null` for to-one linkage
+                    let data = match (next, previous) {
+                        (Some(data), _) => data,
+                        (None, Some(IdentifierData::Multiple(_))) => IdentifierData::Multiple(vec![]),
+                        (None, Some(_)) => IdentifierData::None,
+                        // Only links changed, the linkage is left untouched
+                        (None, None) => continue,
+                    };
+                    let relationship = Relationship {
+                        data: Some(data),
+                        links: None,
+                    };
+                    update
+                        .relationships
+                        .get_or_insert_with(Relationships::new)
+                        .insert(patch.subject.clone(), relationship);
+                }
+                PatchType::Link | PatchType::Meta => {}
+            }
+        }
+
+        Ok(ResourceUpdateDocument {
+            data: update,
+            meta: None,
+            jsonapi: None,
+        })
+    }
+}
+
+impl JsonPatchOperation {
+    /// The JSON Pointer this operation targets
+    pub fn path(&self) -> &str {
+        match *self {
+            JsonPatchOperation::Add { ref path, .. }
+            | JsonPatchOperation::Remove { ref path }
+            | JsonPatchOperation::Replace { ref path, .. }
+            | JsonPatchOperation::Move { ref path, .. }
+            | JsonPatchOperation::Copy { ref path, .. }
+            | JsonPatchOperation::Test { ref path, .. } => path,
+        }
+    }
+}
+
+/// Apply a JSON Patch to `target`, e.g. a serialized `Resource`. Operations
+/// are applied in order and the first one that fails is reported as with
+/// [`PatchSet::from_json_patch`](../api/struct.PatchSet.html#method.from_json_patch).
+/// `target` may be partially patched when an error is returned.
+pub fn apply_json_patch(
+    target: &mut JsonApiValue,
+    json_patch: &[JsonPatchOperation],
+) -> Result<(), DiffPatchError> {
+    for operation in json_patch {
+        apply_json_patch_operation(target, operation)?;
+    }
+    Ok(())
+}
+
+/// The member of a resource that holds the subjects of `patch_type`, and
+/// whether it is present
+fn parent_member(resource: &Resource, patch_type: PatchType) -> (&'static str, bool) {
+    match patch_type {
+        PatchType::Attribute => ("/attributes", true),
+        PatchType::Link => ("/links", resource.links.is_some()),
+        PatchType::Meta => ("/meta", resource.meta.is_some()),
+        PatchType::Relationship | PatchType::RelationshipMember => {
+            ("/relationships", resource.relationships.is_some())
+        }
+    }
+}
+
+fn resource_to_value(resource: &Resource) -> JsonApiValue {
+    serde_json::to_value(resource).expect("Casting Resource to value")
+}
+
+fn member_index(resource: &Resource, patch: &Patch) -> Result<usize, DiffPatchError> {
+    let identifier: ResourceIdentifier = serde_json::from_value(patch.previous.clone())
+        .map_err(|_| DiffPatchError::IncorrectPropertyValue(patch.subject.clone()))?;
+    match resource.get_relationship(&patch.subject).and_then(|r| r.data.as_ref()) {
+        Some(IdentifierData::Multiple(members)) => members
+            .iter()
+            .position(|member| *member == identifier)
+            .ok_or_else(|| DiffPatchError::NonExistentProperty(patch.subject.clone())),
+        _ => Err(DiffPatchError::NonExistentProperty(patch.subject.clone())),
+    }
+}
+
+/// Split a JSON Pointer into the pointer to its parent and its last token
+fn split_pointer(path: &str) -> Result<(&str, String), DiffPatchError> {
+    match path.rfind('/') {
+        Some(idx) => Ok((&path[..idx], unescape_pointer_token(&path[idx + 1..]))),
+        None => Err(DiffPatchError::NonExistentProperty(path.to_string())),
+    }
+}
+
+fn array_index(token: &str, len: usize, path: &str) -> Result<usize, DiffPatchError> {
+    match token.parse::<usize>() {
+        Ok(idx) if idx < len => Ok(idx),
+        _ => Err(DiffPatchError::NonExistentProperty(path.to_string())),
+    }
+}
+
+fn json_patch_add(
+    target: &mut JsonApiValue,
+    path: &str,
+    value: JsonApiValue,
+) -> Result<(), DiffPatchError> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+
+    let (parent, token) = split_pointer(path)?;
+    match target.pointer_mut(parent) {
+        Some(&mut JsonApiValue::Object(ref mut map)) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Some(&mut JsonApiValue::Array(ref mut arr)) => {
+            if token == "-" {
+                arr.push(value);
+            } else {
+                let len = arr.len();
+                let idx = array_index(&token, len + 1, path)?;
+                arr.insert(idx, value);
+            }
+            Ok(())
+        }
+        _ => Err(DiffPatchError::NonExistentProperty(path.to_string())),
+    }
+}
+
+fn json_patch_remove(
+    target: &mut JsonApiValue,
+    path: &str,
+) -> Result<JsonApiValue, DiffPatchError> {
+    let (parent, token) = split_pointer(path)?;
+    match target.pointer_mut(parent) {
+        Some(&mut JsonApiValue::Object(ref mut map)) => map
+            .remove(&token)
+            .ok_or_else(|| DiffPatchError::NonExistentProperty(path.to_string())),
+        Some(&mut JsonApiValue::Array(ref mut arr)) => {
+            let idx = array_index(&token, arr.len(), path)?;
+            Ok(arr.remove(idx))
+        }
+        _ => Err(DiffPatchError::NonExistentProperty(path.to_string())),
+    }
+}
+
+fn apply_json_patch_operation(
+    target: &mut JsonApiValue,
+    operation: &JsonPatchOperation,
+) -> Result<(), DiffPatchError> {
+    match *operation {
+        JsonPatchOperation::Add { ref path, ref value } => {
+            json_patch_add(target, path, value.clone())
+        }
+        JsonPatchOperation::Remove { ref path } => json_patch_remove(target, path).map(|_| ()),
+        JsonPatchOperation::Replace { ref path, ref value } => match target.pointer_mut(path) {
+            Some(current) => {
+                *current = value.clone();
+                Ok(())
+            }
+            None => Err(DiffPatchError::NonExistentProperty(path.clone())),
+        },
+        JsonPatchOperation::Move { ref from, ref path } => {
+            let value = json_patch_remove(target, from)?;
+            json_patch_add(target, path, value)
+        }
+        JsonPatchOperation::Copy { ref from, ref path } => {
+            let value = target
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| DiffPatchError::NonExistentProperty(from.clone()))?;
+            json_patch_add(target, path, value)
+        }
+        JsonPatchOperation::Test { ref path, ref value } => match target.pointer(path) {
+            Some(current) if current == value => Ok(()),
+            Some(_) => Err(DiffPatchError::IncorrectPropertyValue(path.clone())),
+            None => Err(DiffPatchError::NonExistentProperty(path.clone())),
+        },
+    }
+}
@@ -1,6 +1,7 @@
 use queryst::parse;
 use std::collections::HashMap;
 use serde_json::value::Value;
+use crate::errors::{Error, Result};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct PageParams {
@@ -220,6 +221,42 @@ impl Query {
         }
     }
 
+    ///
+    /// Like `from_params`, but fails instead of falling back to defaults if
+    /// the query string can't be parsed or a page parameter is not an
+    /// integer
+    ///
+    /// ```
+    /// use jsonapi::errors::Error;
+    /// use jsonapi::query::Query;
+    ///
+    /// match Query::try_from_params("page[number]=three") {
+    ///     Err(Error::Query { parameter, .. }) => {
+    ///         assert_eq!(parameter, Some("page[number]".into()));
+    ///     }
+    ///     _ => assert!(false),
+    /// }
+    /// ```
+    pub fn try_from_params(params: &str) -> Result<Self> {
+        let o = parse(params).map_err(|err| Error::Query {
+            parameter: None,
+            detail: err.message,
+        })?;
+
+        for &(pointer, parameter) in &[("/page/number", "page[number]"), ("/page/size", "page[size]")] {
+            if let Some(value) = o.pointer(pointer) {
+                if value.as_str().and_then(|v| v.parse::<i64>().ok()).is_none() {
+                    return Err(Error::Query {
+                        parameter: Some(parameter.to_string()),
+                        detail: format!("{} is not an integer", value),
+                    });
+                }
+            }
+        }
+
+        Ok(ok_params(o))
+    }
+
     ///
     /// Builds a query parameter string from a Query
     ///
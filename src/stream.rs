@@ -0,0 +1,492 @@
+//! Incremental serialization and parsing of documents too large to hold in
+//! memory, e.g. exports of whole tables.
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use crate::api::*;
+use crate::errors::{Error, Result};
+use crate::model::JsonApiModel;
+
+/// Writes a document with a collection as primary data one resource at a
+/// time. Primary resources are written as soon as they are added, their
+/// `included` resources are deduplicated and written by
+/// [`finish`](#method.finish).
+///
+/// # Limitations
+///
+/// `included` can only be written once the `data` array is closed, so every
+/// included resource is buffered until `finish`. Memory use grows with the
+/// number of distinct included resources. Exports with a large number of
+/// them should leave out `included` by writing resources with
+/// [`write_resource`](#method.write_resource) and serve related resources
+/// from their own endpoints.
+///
+/// Included resources that are also primary data are only left out with
+/// [`exclude_primary`](#method.exclude_primary), which keeps the `type` and
+/// `id` of every primary resource until `finish`.
+///
+/// Wrap unbuffered writers such as files and sockets in a `BufWriter`.
+///
+/// ```
+/// #[macro_use] extern crate serde_derive;
+/// #[macro_use] extern crate jsonapi;
+/// use jsonapi::api::*;
+/// use jsonapi::model::*;
+/// use jsonapi::stream::*;
+/// use std::str::FromStr;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Flea {
+///     id: String,
+///     name: String,
+/// }
+/// jsonapi_model!(Flea; "flea");
+///
+/// let mut writer = DocumentWriter::new(Vec::new());
+/// for id in 0..3 {
+///     writer.write_model(&Flea { id: id.to_string(), name: "rick".into() }).unwrap();
+/// }
+/// let mut meta = Meta::new();
+/// meta.insert("count".into(), writer.count().into());
+/// let output = writer.meta(meta).finish().unwrap();
+///
+/// let doc = JsonApiDocument::from_str(std::str::from_utf8(&output).unwrap()).unwrap();
+/// assert!(doc.is_valid());
+/// ```
+#[derive(Debug)]
+pub struct DocumentWriter<W: Write> {
+    writer: W,
+    count: usize,
+    primary: Option<HashSet<(String, JsonApiId)>>,
+    included: Vec<Resource>,
+    included_keys: HashSet<(String, JsonApiId)>,
+    links: Option<Links>,
+    meta: Option<Meta>,
+    jsonapi: Option<JsonApiInfo>,
+}
+
+impl<W: Write> DocumentWriter<W> {
+    pub fn new(writer: W) -> Self {
+        DocumentWriter {
+            writer,
+            count: 0,
+            primary: None,
+            included: vec![],
+            included_keys: HashSet::new(),
+            links: None,
+            meta: None,
+            jsonapi: None,
+        }
+    }
+
+    /// Top-level links, written by `finish`
+    pub fn links(mut self, links: Links) -> Self {
+        self.links = Some(links);
+        self
+    }
+
+    /// Top-level meta, written by `finish` so it can describe the whole
+    /// collection
+    pub fn meta(mut self, meta: Meta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// Leave out included resources that are also primary data. Call it
+    /// before writing any resources, earlier ones are not remembered.
+    pub fn exclude_primary(mut self) -> Self {
+        self.primary.get_or_insert_with(HashSet::new);
+        self
+    }
+
+    pub fn jsonapi(mut self, jsonapi: JsonApiInfo) -> Self {
+        self.jsonapi = Some(jsonapi);
+        self
+    }
+
+    /// The number of primary resources written so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Write a resource to the primary data
+    pub fn write_resource(&mut self, resource: &Resource) -> Result<()> {
+        let separator: &[u8] = if self.count == 0 { b"{\"data\":[" } else { b"," };
+        self.writer.write_all(separator)?;
+        serde_json::to_writer(&mut self.writer, resource)?;
+        self.count += 1;
+        if let Some(ref mut primary) = self.primary {
+            primary.insert((resource._type.clone(), resource.id.clone()));
+        }
+        Ok(())
+    }
+
+    /// Write a model to the primary data and add the resources it includes
+    pub fn write_model<T: JsonApiModel>(&mut self, model: &T) -> Result<()> {
+        let (resource, included) = model.to_jsonapi_resource();
+        self.write_resource(&resource)?;
+        for resource in included.unwrap_or_default() {
+            self.include(resource);
+        }
+        Ok(())
+    }
+
+    /// Add a resource to `included` unless a resource with the same `type`
+    /// and `id` was already added
+    pub fn include(&mut self, resource: Resource) {
+        if self.included_keys.insert((resource._type.clone(), resource.id.clone())) {
+            self.included.push(resource);
+        }
+    }
+
+    /// Complete the document and return the writer
+    pub fn finish(mut self) -> Result<W> {
+        let end: &[u8] = if self.count == 0 { b"{\"data\":[]" } else { b"]" };
+        self.writer.write_all(end)?;
+
+        let primary = &self.primary;
+        let included: Vec<&Resource> = self
+            .included
+            .iter()
+            .filter(|resource| {
+                primary
+                    .as_ref()
+                    .is_none_or(|primary| !primary.contains(&(resource._type.clone(), resource.id.clone())))
+            })
+            .collect();
+        if !included.is_empty() {
+            self.writer.write_all(b",\"included\":")?;
+            serde_json::to_writer(&mut self.writer, &included)?;
+        }
+        if let Some(ref links) = self.links {
+            self.writer.write_all(b",\"links\":")?;
+            serde_json::to_writer(&mut self.writer, links)?;
+        }
+        if let Some(ref meta) = self.meta {
+            self.writer.write_all(b",\"meta\":")?;
+            serde_json::to_writer(&mut self.writer, meta)?;
+        }
+        if let Some(ref jsonapi) = self.jsonapi {
+            self.writer.write_all(b",\"jsonapi\":")?;
+            serde_json::to_writer(&mut self.writer, jsonapi)?;
+        }
+
+        self.writer.write_all(b"}")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Write the models as the primary data of a document, the streaming
+/// counterpart of
+/// [`vec_to_jsonapi_document`](../model/fn.vec_to_jsonapi_document.html)
+pub fn write_jsonapi_document<W, I>(writer: W, models: I) -> Result<W>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: JsonApiModel,
+{
+    let mut writer = DocumentWriter::new(writer);
+    for model in models {
+        writer.write_model(&model)?;
+    }
+    writer.finish()
+}
+
+/// Reads a data document from an `io::Read` one primary resource at a time.
+/// Only the resource being read is held in memory, along with the other
+/// top-level members (`included`, `links`, `meta` and `jsonapi`) once they
+/// have been read.
+///
+/// Documents list their members in any order, so `included` may not be
+/// known until every primary resource has been read. Use
+/// [`read_included`](fn.read_included.html) for a first pass over the
+/// document to resolve relationships while reading the primary data in a
+/// second pass.
+///
+/// ```
+/// use jsonapi::stream::*;
+///
+/// let input = r#"{
+///   "data": [
+///     { "type": "fleas", "id": "1", "attributes": { "name": "rick" } },
+///     { "type": "fleas", "id": "2", "attributes": { "name": "morty" } }
+///   ],
+///   "meta": { "count": 2 }
+/// }"#;
+///
+/// let mut reader = DocumentReader::new(input.as_bytes());
+/// let ids: Vec<String> = reader.by_ref().map(|resource| resource.unwrap().id).collect();
+/// assert_eq!(ids, vec!["1", "2"]);
+///
+/// let doc = reader.finish().unwrap();
+/// assert_eq!(doc.meta.unwrap()["count"], 2);
+/// ```
+#[derive(Debug)]
+pub struct DocumentReader<R> {
+    input: BufReader<R>,
+    offset: usize,
+    state: ReaderState,
+    document: DocumentData,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReaderState {
+    Start,
+    Members { first: bool },
+    Data { index: usize },
+    Done,
+}
+
+impl<R: Read> DocumentReader<R> {
+    pub fn new(reader: R) -> Self {
+        DocumentReader {
+            input: BufReader::new(reader),
+            offset: 0,
+            state: ReaderState::Start,
+            document: DocumentData::default(),
+        }
+    }
+
+    /// The `included` resources, if they have been read
+    pub fn included(&self) -> Option<&Resources> {
+        self.document.included.as_ref()
+    }
+
+    /// Read the next primary resource, or `None` once the primary data has
+    /// been read
+    pub fn next_resource(&mut self) -> Result<Option<Resource>> {
+        let result = self.advance(true);
+        if result.is_err() {
+            self.state = ReaderState::Done;
+        }
+        result
+    }
+
+    /// Read the rest of the document, skipping any primary resources left,
+    /// and return the other top-level members
+    pub fn finish(mut self) -> Result<DocumentData> {
+        while self.advance(false)?.is_some() {}
+        Ok(self.document)
+    }
+
+    /// Move on to the next primary resource, deserializing it if `keep`
+    fn advance(&mut self, keep: bool) -> Result<Option<Resource>> {
+        loop {
+            match self.state {
+                ReaderState::Start => {
+                    self.expect(b'{')?;
+                    self.state = ReaderState::Members { first: true };
+                }
+                ReaderState::Members { first } => {
+                    self.skip_whitespace()?;
+                    if self.peek()? == Some(b'}') {
+                        self.bump()?;
+                        self.skip_whitespace()?;
+                        if self.peek()?.is_some() {
+                            return Err(self.syntax_error("Trailing characters"));
+                        }
+                        self.state = ReaderState::Done;
+                        continue;
+                    }
+                    if !first {
+                        self.expect(b',')?;
+                    }
+                    let key: String = self.parse_value("")?;
+                    self.expect(b':')?;
+                    self.state = ReaderState::Members { first: false };
+                    if key == "data" {
+                        self.skip_whitespace()?;
+                        if self.peek()? == Some(b'[') {
+                            self.bump()?;
+                            self.state = ReaderState::Data { index: 0 };
+                            continue;
+                        }
+                        if !keep {
+                            self.skip_value("/data")?;
+                            continue;
+                        }
+                        let resource: Option<Resource> = self.parse_value("/data")?;
+                        if resource.is_some() {
+                            return Ok(resource);
+                        }
+                        continue;
+                    }
+                    self.read_member(&key)?;
+                }
+                ReaderState::Data { index } => {
+                    self.skip_whitespace()?;
+                    if self.peek()? == Some(b']') {
+                        self.bump()?;
+                        self.state = ReaderState::Members { first: false };
+                        continue;
+                    }
+                    if index > 0 {
+                        self.expect(b',')?;
+                    }
+                    self.state = ReaderState::Data { index: index + 1 };
+                    let pointer = format!("/data/{}", index);
+                    if keep {
+                        return self.parse_value(&pointer).map(Some);
+                    }
+                    self.skip_value(&pointer)?;
+                }
+                ReaderState::Done => return Ok(None),
+            }
+        }
+    }
+
+    fn read_member(&mut self, key: &str) -> Result<()> {
+        let pointer = format!("/{}", escape_pointer_token(key));
+        match key {
+            "included" => self.document.included = self.parse_value(&pointer)?,
+            "links" => self.document.links = self.parse_value(&pointer)?,
+            "meta" => self.document.meta = self.parse_value(&pointer)?,
+            "jsonapi" => self.document.jsonapi = self.parse_value(&pointer)?,
+            "errors" => {
+                return Err(Error::Parse {
+                    pointer: Some(pointer),
+                    detail: "Expected a document with primary data".to_string(),
+                })
+            }
+            // Other members, e.g. those of extensions, are skipped
+            _ => self.scan_value(&mut Vec::new())?,
+        }
+        Ok(())
+    }
+
+    /// Read the next value and deserialize it, reporting failures with a
+    /// JSON Pointer relative to `pointer`
+    fn parse_value<T: serde::de::DeserializeOwned>(&mut self, pointer: &str) -> Result<T> {
+        let mut buffer = Vec::new();
+        self.scan_value(&mut buffer)?;
+        let value: JsonApiValue = serde_json::from_slice(&buffer).map_err(|err| Error::Parse {
+            pointer: Some(pointer.to_string()),
+            detail: err.to_string(),
+        })?;
+        deserialize_tracked(value, pointer)
+    }
+
+    /// Read the next value without deserializing it, only checking its
+    /// syntax
+    fn skip_value(&mut self, pointer: &str) -> Result<()> {
+        let mut buffer = Vec::new();
+        self.scan_value(&mut buffer)?;
+        serde_json::from_slice::<serde::de::IgnoredAny>(&buffer).map_err(|err| Error::Parse {
+            pointer: Some(pointer.to_string()),
+            detail: err.to_string(),
+        })?;
+        Ok(())
+    }
+
+    /// Copy the bytes of the next value into `buffer`. Only the extent of the
+    /// value is determined here, its syntax is checked when it is parsed.
+    fn scan_value(&mut self, buffer: &mut Vec<u8>) -> Result<()> {
+        self.skip_whitespace()?;
+        match self.peek()? {
+            Some(b'{') | Some(b'[') => {
+                let mut depth = 0;
+                loop {
+                    let byte = self.next_byte()?;
+                    buffer.push(byte);
+                    match byte {
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        b'"' => self.scan_string(buffer)?,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+            }
+            Some(b'"') => {
+                buffer.push(self.next_byte()?);
+                self.scan_string(buffer)
+            }
+            Some(_) => {
+                while let Some(byte) = self.peek()? {
+                    if matches!(byte, b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r') {
+                        break;
+                    }
+                    buffer.push(self.next_byte()?);
+                }
+                Ok(())
+            }
+            None => Err(self.syntax_error("Unexpected end of input")),
+        }
+    }
+
+    /// Copy the rest of a string whose opening quote was read
+    fn scan_string(&mut self, buffer: &mut Vec<u8>) -> Result<()> {
+        loop {
+            let byte = self.next_byte()?;
+            buffer.push(byte);
+            match byte {
+                b'\\' => buffer.push(self.next_byte()?),
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.input.fill_buf()?.first().copied())
+    }
+
+    fn bump(&mut self) -> Result<()> {
+        self.input.consume(1);
+        self.offset += 1;
+        Ok(())
+    }
+
+    fn next_byte(&mut self) -> Result<u8> {
+        match self.peek()? {
+            Some(byte) => {
+                self.bump()?;
+                Ok(byte)
+            }
+            None => Err(self.syntax_error("Unexpected end of input")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> Result<()> {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek()? {
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        self.skip_whitespace()?;
+        match self.peek()? {
+            Some(byte) if byte == expected => self.bump(),
+            Some(byte) => Err(self.syntax_error(&format!(
+                "Expected '{}', found '{}'",
+                expected as char, byte as char
+            ))),
+            None => Err(self.syntax_error("Unexpected end of input")),
+        }
+    }
+
+    fn syntax_error(&self, detail: &str) -> Error {
+        Error::Parse {
+            pointer: None,
+            detail: format!("{} at byte {}", detail, self.offset),
+        }
+    }
+}
+
+impl<R: Read> Iterator for DocumentReader<R> {
+    type Item = Result<Resource>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_resource().transpose()
+    }
+}
+
+/// Read the `included` resources of a document, skipping over its primary
+/// data without deserializing it
+pub fn read_included<R: Read>(reader: R) -> Result<IncludedIndex> {
+    let doc = DocumentReader::new(reader).finish()?;
+    Ok(IncludedIndex::from_document(&doc))
+}
@@ -0,0 +1,206 @@
+//! Extractors and responders for [actix-web](https://docs.rs/actix-web),
+//! enabled with the `actix-web` cargo feature.
+//!
+//! [`JsonApi`](struct.JsonApi.html) extracts a document or model from the
+//! request body after checking the `Content-Type` and `Accept` headers, and
+//! responds with a document or model. [`JsonApiQuery`](struct.JsonApiQuery.html)
+//! extracts the query parameters. Failures are rejected with JSON:API error
+//! documents. To support extensions, register a
+//! [`Negotiator`](../../media_type/struct.Negotiator.html) with `App::app_data`.
+//!
+//! Responses use the media type negotiated from the `Accept` header as their
+//! `Content-Type`, including its `ext` and `profile` parameters, and add the
+//! extensions the response document declares to `ext`. Extract a
+//! [`JsonApiMediaType`](struct.JsonApiMediaType.html) to inspect it.
+//!
+//! ```
+//! # extern crate actix_web;
+//! use actix_web::{web, App};
+//! use jsonapi::api::*;
+//! use jsonapi::web::actix::*;
+//!
+//! async fn create(JsonApi(doc): JsonApi<NewResourceDocument>) -> Result<JsonApiDocument, jsonapi::errors::Error> {
+//!     match doc.data.validate("articles", false) {
+//!         Some(errors) => Err(errors.into()),
+//!         None => Ok(JsonApiDocument::Data(DocumentData {
+//!             data: Some(PrimaryData::Single(Box::new(doc.data.into_resource("1".into())))),
+//!             ..Default::default()
+//!         })),
+//!     }
+//! }
+//!
+//! let app = App::new().route("/articles", web::post().to(create));
+//! ```
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use ::actix_web::body::BoxBody;
+use ::actix_web::dev::Payload;
+use ::actix_web::error::InternalError;
+use ::actix_web::http::header::{HeaderName, ACCEPT, CONTENT_TYPE};
+use ::actix_web::http::StatusCode;
+use ::actix_web::web::Bytes;
+use ::actix_web::{FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder, ResponseError};
+use crate::api::*;
+use crate::api_error::HttpStatus;
+use crate::errors::Error;
+use crate::media_type::{MediaType, NegotiationError, Negotiator};
+use crate::query::Query;
+use super::{negotiate, response_media_type, FromRequestBody, IntoResponseDocument};
+
+/// Extracts `T` from a JSON:API request body, or responds with `T` as a
+/// JSON:API document
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApi<T>(pub T);
+
+/// Extracts the JSON:API query parameters of the request URI, rejecting
+/// invalid ones with a `400 Bad Request`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApiQuery(pub Query);
+
+/// Extracts the media type of the response negotiated from the `Accept`
+/// header, rejecting requests that accept no supported media type with a
+/// `406 Not Acceptable`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApiMediaType(pub MediaType);
+
+impl<T> FromRequest for JsonApi<T>
+where
+    T: FromRequestBody + 'static,
+{
+    type Error = ::actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let negotiator = req.app_data::<Negotiator>().cloned().unwrap_or_default();
+        match negotiate(
+            &negotiator,
+            Some(header(req, CONTENT_TYPE).unwrap_or("")),
+            header(req, ACCEPT),
+        ) {
+            Ok(media_type) => {
+                req.extensions_mut().insert(media_type);
+            }
+            Err(err) => return Box::pin(ready(Err(rejection(err)))),
+        }
+
+        let body = Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let body = body.await.map_err(|err| {
+                let status = HttpStatus::new(err.as_response_error().status_code().as_u16())
+                    .unwrap_or(HttpStatus::BAD_REQUEST);
+                let error = JsonApiError::builder(status).detail(&err.to_string()).build();
+                rejection(DocumentError::from_errors(vec![error]))
+            })?;
+
+            T::from_request_body(&body).map(JsonApi).map_err(rejection)
+        })
+    }
+}
+
+impl FromRequest for JsonApiQuery {
+    type Error = ::actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            Query::try_from_params(req.query_string())
+                .map(JsonApiQuery)
+                .map_err(rejection),
+        )
+    }
+}
+
+impl FromRequest for JsonApiMediaType {
+    type Error = ::actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(negotiated_media_type(req).map(JsonApiMediaType).map_err(rejection))
+    }
+}
+
+fn header(req: &HttpRequest, name: HeaderName) -> Option<&str> {
+    req.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+/// The media type negotiated for the response to `req`, remembered in the
+/// request extensions once negotiated
+fn negotiated_media_type(req: &HttpRequest) -> Result<MediaType, NegotiationError> {
+    if let Some(media_type) = req.extensions().get::<MediaType>() {
+        return Ok(media_type.clone());
+    }
+    let negotiator = req.app_data::<Negotiator>().cloned().unwrap_or_default();
+    let media_type = negotiate(&negotiator, None, header(req, ACCEPT))?;
+    req.extensions_mut().insert(media_type.clone());
+    Ok(media_type)
+}
+
+/// An actix-web error that responds with the JSON:API document of `body`
+fn rejection<T: IntoResponseDocument>(body: T) -> ::actix_web::Error {
+    let response = document_response(body, &MediaType::jsonapi());
+    let reason = response.status().canonical_reason().unwrap_or("Invalid request");
+    InternalError::from_response(reason, response).into()
+}
+
+fn document_response<T: IntoResponseDocument>(body: T, media_type: &MediaType) -> HttpResponse {
+    let status = StatusCode::from_u16(body.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let document = body.into_response_document();
+    match serde_json::to_vec(&document) {
+        Ok(bytes) => HttpResponse::build(status)
+            .content_type(response_media_type(media_type, &document).to_string())
+            .body(bytes),
+        Err(err) => {
+            warn!("Could not serialize response document: {}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Respond with `body` in the media type negotiated for `req`. Requests that
+/// accept no supported media type are answered with a `406 Not Acceptable`.
+fn respond<T: IntoResponseDocument>(body: T, req: &HttpRequest) -> HttpResponse {
+    match negotiated_media_type(req) {
+        Ok(media_type) => document_response(body, &media_type),
+        Err(err) => document_response(err, &MediaType::jsonapi()),
+    }
+}
+
+impl<T: IntoResponseDocument> Responder for JsonApi<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        respond(self.0, req)
+    }
+}
+
+impl Responder for JsonApiDocument {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        respond(self, req)
+    }
+}
+
+impl Responder for DocumentError {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        respond(self, req)
+    }
+}
+
+/// Handlers can return `Result<_, jsonapi::errors::Error>`, failures are
+/// sent as error documents
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(IntoResponseDocument::status(self)).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let body = DocumentError {
+            errors: self.to_jsonapi_errors(),
+            ..Default::default()
+        };
+        document_response(body, &MediaType::jsonapi())
+    }
+}
@@ -0,0 +1,200 @@
+//! Extractors and responses for [axum](https://docs.rs/axum), enabled with
+//! the `axum` cargo feature.
+//!
+//! [`JsonApi`](struct.JsonApi.html) extracts a document or model from the
+//! request body after checking the `Content-Type` and `Accept` headers, and
+//! responds with a document or model. [`JsonApiQuery`](struct.JsonApiQuery.html)
+//! extracts the query parameters. Failures are rejected with JSON:API error
+//! documents. To support extensions, add a
+//! [`Negotiator`](../../media_type/struct.Negotiator.html) to the request
+//! extensions, e.g. with `axum::Extension`.
+//!
+//! Responses use the JSON:API media type, with the extensions the response
+//! document declares as its `ext` parameter. To respond
+//! with the media type negotiated from the `Accept` header, including its
+//! `ext` and `profile` parameters, extract a
+//! [`JsonApiMediaType`](struct.JsonApiMediaType.html) and return it along
+//! with the response, e.g. `(media_type, JsonApi(doc))`.
+//!
+//! ```
+//! # extern crate axum;
+//! use axum::{routing::post, Router};
+//! use jsonapi::api::*;
+//! use jsonapi::web::axum::*;
+//!
+//! async fn create(JsonApi(doc): JsonApi<NewResourceDocument>) -> Result<JsonApiDocument, DocumentError> {
+//!     match doc.data.validate("articles", false) {
+//!         Some(errors) => Err(DocumentError::from(errors.as_slice())),
+//!         None => Ok(JsonApiDocument::Data(DocumentData {
+//!             data: Some(PrimaryData::Single(Box::new(doc.data.into_resource("1".into())))),
+//!             ..Default::default()
+//!         })),
+//!     }
+//! }
+//!
+//! let app: Router = Router::new().route("/articles", post(create));
+//! ```
+use ::axum::body::Bytes;
+use ::axum::extract::{FromRequest, FromRequestParts, Request};
+use ::axum::http::header::{HeaderMap, ACCEPT, CONTENT_TYPE};
+use ::axum::http::request::Parts;
+use ::axum::http::{HeaderValue, StatusCode};
+use ::axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use crate::api::*;
+use crate::api_error::HttpStatus;
+use crate::errors::Error;
+use crate::media_type::{MediaType, NegotiationError, Negotiator};
+use crate::query::Query;
+use super::{negotiate, response_media_type, FromRequestBody, IntoResponseDocument};
+
+/// Extracts `T` from a JSON:API request body, or responds with `T` as a
+/// JSON:API document
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApi<T>(pub T);
+
+/// Extracts the JSON:API query parameters of the request URI, rejecting
+/// invalid ones with a `400 Bad Request`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApiQuery(pub Query);
+
+/// Extracts the media type of the response negotiated from the `Accept`
+/// header, rejecting requests that accept no supported media type with a
+/// `406 Not Acceptable`. Returned along with a response it becomes the
+/// `Content-Type` of the response, keeping the extensions the response
+/// document declares.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonApiMediaType(pub MediaType);
+
+impl<T, S> FromRequest<S> for JsonApi<T>
+where
+    T: FromRequestBody,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let negotiator = req.extensions().get::<Negotiator>().cloned().unwrap_or_default();
+        let headers = req.headers();
+        negotiate(
+            &negotiator,
+            Some(header(headers, CONTENT_TYPE).unwrap_or("")),
+            header(headers, ACCEPT),
+        )
+        .map_err(IntoResponse::into_response)?;
+
+        let body = Bytes::from_request(req, state).await.map_err(|rejection| {
+            let status = HttpStatus::new(rejection.status().as_u16()).unwrap_or(HttpStatus::BAD_REQUEST);
+            let error = JsonApiError::builder(status).detail(&rejection.body_text()).build();
+            DocumentError::from_errors(vec![error]).into_response()
+        })?;
+
+        T::from_request_body(&body)
+            .map(JsonApi)
+            .map_err(IntoResponse::into_response)
+    }
+}
+
+impl<S> FromRequestParts<S> for JsonApiQuery
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Query::try_from_params(parts.uri.query().unwrap_or(""))
+            .map(JsonApiQuery)
+            .map_err(IntoResponse::into_response)
+    }
+}
+
+impl<S> FromRequestParts<S> for JsonApiMediaType
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let negotiator = parts.extensions.get::<Negotiator>().cloned().unwrap_or_default();
+        negotiate(&negotiator, None, header(&parts.headers, ACCEPT))
+            .map(JsonApiMediaType)
+            .map_err(IntoResponse::into_response)
+    }
+}
+
+/// Sets the `Content-Type` of the response. Media types that are not valid
+/// header values leave it unchanged.
+impl IntoResponseParts for JsonApiMediaType {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let mut media_type = self.0;
+        let current = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<MediaType>().ok());
+        for uri in current.into_iter().flat_map(|current| current.ext) {
+            if !media_type.ext.contains(&uri) {
+                media_type.ext.push(uri);
+            }
+        }
+        match HeaderValue::from_str(&media_type.to_string()) {
+            Ok(value) => {
+                res.headers_mut().insert(CONTENT_TYPE, value);
+            }
+            Err(_) => warn!("Media type {} is not a valid header value", media_type),
+        }
+        Ok(res)
+    }
+}
+
+fn header(headers: &HeaderMap, name: ::axum::http::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn document_response<T: IntoResponseDocument>(body: T) -> Response {
+    let status = StatusCode::from_u16(body.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let document = body.into_response_document();
+    let media_type = response_media_type(&MediaType::jsonapi(), &document);
+    match (serde_json::to_vec(&document), HeaderValue::from_str(&media_type.to_string())) {
+        (Ok(bytes), Ok(content_type)) => (status, [(CONTENT_TYPE, content_type)], bytes).into_response(),
+        (_, Err(_)) => {
+            warn!("Media type {} is not a valid header value", media_type);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        (Err(err), _) => {
+            warn!("Could not serialize response document: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+impl<T: IntoResponseDocument> IntoResponse for JsonApi<T> {
+    fn into_response(self) -> Response {
+        document_response(self.0)
+    }
+}
+
+impl IntoResponse for JsonApiDocument {
+    fn into_response(self) -> Response {
+        document_response(self)
+    }
+}
+
+impl IntoResponse for DocumentError {
+    fn into_response(self) -> Response {
+        document_response(self)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        document_response(self)
+    }
+}
+
+impl IntoResponse for NegotiationError {
+    fn into_response(self) -> Response {
+        document_response(self)
+    }
+}
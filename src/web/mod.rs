@@ -0,0 +1,170 @@
+//! Framework independent plumbing for serving JSON:API over HTTP: reading
+//! request bodies, negotiating media types and choosing the status and body
+//! of responses. The integrations with web frameworks are behind cargo
+//! features:
+//!
+//! * `axum`: extractors and responses for [axum](https://docs.rs/axum), see
+//!   [`web::axum`](axum/index.html)
+//! * `actix-web`: extractors and responders for
+//!   [actix-web](https://docs.rs/actix-web), see
+//!   [`web::actix`](actix/index.html)
+use std::str::FromStr;
+use crate::api::*;
+use crate::api_error::IntoJsonApiError;
+use crate::errors::{Error, Result};
+use crate::media_type::{MediaType, NegotiationError, Negotiator};
+use crate::model::JsonApiModel;
+
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "actix-web")]
+pub mod actix;
+
+/// Types that can be read from the body of a JSON:API request
+pub trait FromRequestBody: Sized {
+    fn from_request_body(body: &[u8]) -> Result<Self>;
+}
+
+/// Types that can be sent as the body of a JSON:API response
+pub trait IntoResponseDocument {
+    /// The HTTP status code of the response
+    fn status(&self) -> u16;
+
+    fn into_response_document(self) -> JsonApiDocument;
+}
+
+fn body_str(body: &[u8]) -> Result<&str> {
+    std::str::from_utf8(body).map_err(|err| Error::Parse {
+        pointer: None,
+        detail: err.to_string(),
+    })
+}
+
+/// The document must be valid, see
+/// [`JsonApiDocument::validate`](../api/enum.JsonApiDocument.html#method.validate)
+impl FromRequestBody for JsonApiDocument {
+    fn from_request_body(body: &[u8]) -> Result<Self> {
+        let doc = JsonApiDocument::from_str(body_str(body)?)?;
+        match doc.validate() {
+            Some(errors) => Err(Error::Document(errors)),
+            None => Ok(doc),
+        }
+    }
+}
+
+impl FromRequestBody for NewResourceDocument {
+    fn from_request_body(body: &[u8]) -> Result<Self> {
+        NewResourceDocument::from_str(body_str(body)?)
+    }
+}
+
+impl FromRequestBody for ResourceUpdateDocument {
+    fn from_request_body(body: &[u8]) -> Result<Self> {
+        ResourceUpdateDocument::from_str(body_str(body)?)
+    }
+}
+
+impl FromRequestBody for RelationshipDocument {
+    fn from_request_body(body: &[u8]) -> Result<Self> {
+        RelationshipDocument::from_str(body_str(body)?)
+    }
+}
+
+/// Models are read from the primary data of the document, resolving
+/// relationships against its `included` resources
+impl<T: JsonApiModel> FromRequestBody for T {
+    fn from_request_body(body: &[u8]) -> Result<Self> {
+        match JsonApiDocument::from_request_body(body)? {
+            JsonApiDocument::Data(ref data) => T::from_jsonapi_document(data),
+            JsonApiDocument::Error(_) => Err(Error::Parse {
+                pointer: None,
+                detail: "Expected a document with primary data".to_string(),
+            }),
+        }
+    }
+}
+
+/// `200` for data documents, the status of the errors for error documents
+impl IntoResponseDocument for JsonApiDocument {
+    fn status(&self) -> u16 {
+        match *self {
+            JsonApiDocument::Data(_) => 200,
+            JsonApiDocument::Error(ref errors) => DocumentError::status(errors).unwrap_or(500),
+        }
+    }
+
+    fn into_response_document(self) -> JsonApiDocument {
+        self
+    }
+}
+
+impl IntoResponseDocument for DocumentError {
+    fn status(&self) -> u16 {
+        DocumentError::status(self).unwrap_or(500)
+    }
+
+    fn into_response_document(self) -> JsonApiDocument {
+        JsonApiDocument::Error(self)
+    }
+}
+
+impl IntoResponseDocument for Error {
+    fn status(&self) -> u16 {
+        IntoJsonApiError::status(self)
+    }
+
+    fn into_response_document(self) -> JsonApiDocument {
+        JsonApiDocument::Error(self.into())
+    }
+}
+
+impl IntoResponseDocument for NegotiationError {
+    fn status(&self) -> u16 {
+        NegotiationError::status(self)
+    }
+
+    fn into_response_document(self) -> JsonApiDocument {
+        JsonApiDocument::Error(DocumentError::from_errors(vec![self]))
+    }
+}
+
+impl<T: JsonApiModel> IntoResponseDocument for T {
+    fn status(&self) -> u16 {
+        200
+    }
+
+    fn into_response_document(self) -> JsonApiDocument {
+        self.to_jsonapi_document()
+    }
+}
+
+/// The `Content-Type` of a response with `document`: the `negotiated` media
+/// type, advertising the extensions the document declares in its `jsonapi`
+/// member as well
+pub fn response_media_type(negotiated: &MediaType, document: &JsonApiDocument) -> MediaType {
+    let jsonapi = match *document {
+        JsonApiDocument::Data(ref data) => data.jsonapi.as_ref(),
+        JsonApiDocument::Error(ref errors) => errors.jsonapi.as_ref(),
+    };
+    let mut media_type = negotiated.clone();
+    for uri in jsonapi.and_then(|info| info.ext.as_ref()).into_iter().flatten() {
+        if !media_type.ext.contains(uri) {
+            media_type.ext.push(uri.clone());
+        }
+    }
+    media_type
+}
+
+/// Negotiate the media types of a request: check its `Content-Type`, which
+/// is `None` for requests without a body, and choose the media type of the
+/// response from its `Accept` header
+pub fn negotiate(
+    negotiator: &Negotiator,
+    content_type: Option<&str>,
+    accept: Option<&str>,
+) -> std::result::Result<MediaType, NegotiationError> {
+    if let Some(content_type) = content_type {
+        negotiator.check_content_type(content_type)?;
+    }
+    negotiator.negotiate(accept)
+}
@@ -0,0 +1,242 @@
+#![cfg(feature = "actix-web")]
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate actix_web;
+extern crate tokio;
+extern crate env_logger;
+
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::{call_service, init_service, read_body, TestRequest};
+use actix_web::{web, App};
+use jsonapi::api::*;
+use jsonapi::errors::Error;
+use jsonapi::media_type::Negotiator;
+use jsonapi::model::*;
+use jsonapi::web::actix::*;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Dog {
+    id: String,
+    name: String,
+}
+jsonapi_model!(Dog; "dogs");
+
+const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+const DOG: &str = r#"{ "data": { "type": "dogs", "id": "1", "attributes": { "name": "rex" } } }"#;
+
+const DECLARED: &str = r#"{
+    "jsonapi": { "version": "1.1", "ext": ["https://jsonapi.org/ext/atomic"] },
+    "data": { "type": "dogs", "id": "1", "attributes": { "name": "Rex" } }
+}"#;
+
+async fn rename(JsonApi(mut dog): JsonApi<Dog>) -> JsonApi<Dog> {
+    dog.name = dog.name.to_uppercase();
+    JsonApi(dog)
+}
+
+async fn echo(JsonApi(doc): JsonApi<JsonApiDocument>) -> JsonApiDocument {
+    doc
+}
+
+async fn page_size(JsonApiQuery(query): JsonApiQuery) -> String {
+    query.page.map(|page| page.size).unwrap_or_default().to_string()
+}
+
+async fn missing() -> DocumentError {
+    DocumentError::not_found("dogs", "7")
+}
+
+async fn conflict() -> Result<JsonApi<Dog>, Error> {
+    Err(Error::Conversion {
+        resource_type: Some("dogs".into()),
+        id: Some("1".into()),
+        field: Some("name".into()),
+        detail: "Name is taken".into(),
+    })
+}
+
+fn app(negotiator: Negotiator) -> App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    App::new()
+        .app_data(negotiator)
+        .route("/dogs", web::post().to(rename))
+        .route("/dogs", web::get().to(page_size))
+        .route("/dogs/1", web::patch().to(conflict))
+        .route("/dogs/7", web::get().to(missing))
+        .route("/echo", web::post().to(echo))
+}
+
+async fn send(negotiator: Negotiator, request: TestRequest) -> (StatusCode, Option<String>, serde_json::Value) {
+    let service = init_service(app(negotiator)).await;
+    let response = call_service(&service, request.to_request()).await;
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let bytes = read_body(response).await;
+    let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, content_type, body)
+}
+
+fn post_request(uri: &str, content_type: &str, body: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(uri)
+        .insert_header((header::CONTENT_TYPE, content_type))
+        .set_payload(body.to_string())
+}
+
+#[tokio::test]
+async fn it_extracts_and_responds_with_models() {
+    let _ = env_logger::try_init();
+    let (status, content_type, body) = send(Negotiator::new(), post_request("/dogs", MEDIA_TYPE, DOG)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["data"]["attributes"]["name"], "REX");
+}
+
+#[tokio::test]
+async fn it_extracts_documents() {
+    let _ = env_logger::try_init();
+    let (status, _, body) = send(Negotiator::new(), post_request("/echo", MEDIA_TYPE, DOG)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::from_str::<serde_json::Value>(DOG).unwrap());
+
+    // Documents must be valid
+    let (status, _, body) = send(Negotiator::new(), post_request("/echo", MEDIA_TYPE, r#"{ "included": [] }"#)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errors"].as_array().map(Vec::len), Some(2));
+}
+
+#[tokio::test]
+async fn it_rejects_invalid_bodies_with_error_documents() {
+    let _ = env_logger::try_init();
+    let (status, content_type, body) = send(
+        Negotiator::new(),
+        post_request("/dogs", MEDIA_TYPE, r#"{ "data": { "type": "dogs", "id": 1 } }"#),
+    ).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["errors"][0]["source"]["pointer"], "/data/id");
+
+    let (status, _, body) = send(
+        Negotiator::new(),
+        post_request("/dogs", MEDIA_TYPE, r#"{ "data": { "type": "dogs", "id": "1", "attributes": {} } }"#),
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["status"], "422");
+}
+
+#[tokio::test]
+async fn it_negotiates_media_types() {
+    let _ = env_logger::try_init();
+    let (status, _, body) = send(Negotiator::new(), post_request("/dogs", "application/json", DOG)).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body["errors"][0]["source"]["header"], "Content-Type");
+
+    let request = post_request("/dogs", MEDIA_TYPE, DOG)
+        .insert_header((header::ACCEPT, "application/vnd.api+json; charset=utf-8"));
+    let (status, _, body) = send(Negotiator::new(), request).await;
+    assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+    assert_eq!(body["errors"][0]["source"]["header"], "Accept");
+
+    // Extensions are supported once a negotiator declares them
+    let ext = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#;
+    let (status, _, _) = send(Negotiator::new(), post_request("/dogs", ext, DOG)).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let negotiator = Negotiator::new().support_extension("https://jsonapi.org/ext/atomic");
+    let (status, _, _) = send(negotiator, post_request("/dogs", ext, DOG)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn it_responds_with_the_negotiated_media_type() {
+    let _ = env_logger::try_init();
+    let ext = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#;
+    let negotiator = || {
+        Negotiator::new()
+            .support_extension("https://jsonapi.org/ext/atomic")
+            .support_profile("https://example.com/profile")
+    };
+    let accept = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"; profile="https://example.com/profile https://example.com/other""#;
+    let request = post_request("/dogs", MEDIA_TYPE, DOG).insert_header((header::ACCEPT, accept));
+    let (status, content_type, _) = send(negotiator(), request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        content_type.as_deref(),
+        Some(r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"; profile="https://example.com/profile""#)
+    );
+
+    // Responses of handlers that don't read the body are negotiated too
+    let request = TestRequest::get().uri("/dogs/7").insert_header((header::ACCEPT, accept));
+    let (status, content_type, _) = send(negotiator(), request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(content_type.unwrap().contains("ext="));
+
+    // Without an `Accept` header the media type has no parameters
+    let (_, content_type, _) = send(negotiator(), post_request("/dogs", ext, DOG)).await;
+    assert_eq!(content_type.as_deref(), Some(MEDIA_TYPE));
+}
+
+#[tokio::test]
+async fn it_advertises_declared_extensions() {
+    let _ = env_logger::try_init();
+    let (status, content_type, _) = send(Negotiator::new(), post_request("/echo", MEDIA_TYPE, DECLARED)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        content_type.as_deref(),
+        Some(r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#)
+    );
+
+    // Along with the negotiated ones
+    let negotiator = Negotiator::new().support_profile("https://example.com/profile");
+    let request = post_request("/echo", MEDIA_TYPE, DECLARED)
+        .insert_header((header::ACCEPT, r#"application/vnd.api+json; profile="https://example.com/profile""#));
+    let (_, content_type, _) = send(negotiator, request).await;
+    assert_eq!(
+        content_type.as_deref(),
+        Some(r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"; profile="https://example.com/profile""#)
+    );
+}
+
+#[tokio::test]
+async fn it_extracts_queries() {
+    let _ = env_logger::try_init();
+    let service = init_service(app(Negotiator::new())).await;
+    let request = TestRequest::get().uri("/dogs?page[size]=5").to_request();
+    let response = call_service(&service, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(&read_body(response).await[..], b"5");
+
+    let (status, _, body) = send(Negotiator::new(), TestRequest::get().uri("/dogs?page[size]=five")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errors"][0]["source"]["parameter"], "page[size]");
+}
+
+#[tokio::test]
+async fn it_responds_with_error_documents() {
+    let _ = env_logger::try_init();
+    let (status, content_type, body) = send(Negotiator::new(), TestRequest::get().uri("/dogs/7")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["errors"][0]["title"], "Not Found");
+
+    let (status, content_type, body) = send(Negotiator::new(), TestRequest::patch().uri("/dogs/1")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["errors"][0]["title"], "Invalid resource");
+}
@@ -0,0 +1,347 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::api_error::*;
+use jsonapi::errors;
+use jsonapi::model::*;
+use std::error::Error as _;
+use std::str::FromStr;
+
+mod helper;
+use crate::helper::read_json_file;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Dog {
+    id: String,
+    name: String,
+}
+jsonapi_model!(Dog; "dogs");
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+    Forbidden(Option<String>),
+    NotFound,
+    Conversion,
+    Other(u16),
+}
+
+impl From<errors::Error> for AppError {
+    fn from(_: errors::Error) -> Self {
+        AppError::Conversion
+    }
+}
+
+impl IntoJsonApiError for AppError {
+    fn status(&self) -> u16 {
+        match *self {
+            AppError::Forbidden(_) => 403,
+            AppError::NotFound => 404,
+            AppError::Conversion => 500,
+            AppError::Other(status) => status,
+        }
+    }
+
+    fn code(&self) -> Option<String> {
+        match *self {
+            AppError::Forbidden(_) => Some("forbidden".into()),
+            AppError::NotFound => Some("not_found".into()),
+            _ => None,
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        match *self {
+            AppError::Forbidden(ref detail) => detail.clone(),
+            _ => None,
+        }
+    }
+}
+
+fn registry() -> ErrorRegistry<AppError> {
+    ErrorRegistry::new(|e: &JsonApiError| AppError::Other(e.status()))
+        .register("forbidden", |e| AppError::Forbidden(e.detail.clone()))
+        .register("not_found", |_| AppError::NotFound)
+}
+
+#[test]
+fn it_converts_application_errors_to_error_documents() {
+    let _ = env_logger::try_init();
+    let doc = DocumentError::from_errors(vec![
+        AppError::Forbidden(Some("No dogs allowed".into())),
+        AppError::NotFound,
+    ]);
+    assert_eq!(doc.errors.len(), 2);
+    assert_eq!(doc.errors[0].status, Some("403".into()));
+    assert_eq!(doc.errors[0].code, Some("forbidden".into()));
+    assert_eq!(doc.errors[0].detail, Some("No dogs allowed".into()));
+    assert_eq!(doc.errors[1].status, Some("404".into()));
+    assert_eq!(doc.status(), Some(400));
+
+    let doc = DocumentError::from_errors(vec![AppError::NotFound, AppError::NotFound]);
+    assert_eq!(doc.status(), Some(404));
+
+    let doc = DocumentError::from_errors(vec![AppError::NotFound, AppError::Conversion]);
+    assert_eq!(doc.status(), Some(500));
+
+    let doc = DocumentError::from_errors(Vec::<AppError>::new());
+    assert_eq!(doc.status(), None);
+}
+
+#[test]
+fn it_maps_error_documents_to_application_errors() {
+    let _ = env_logger::try_init();
+    let s = read_json_file("data/errors.json");
+    let doc: Result<DocumentError, _> = serde_json::from_str(&s);
+    assert!(doc.is_ok());
+    let doc = doc.unwrap();
+    assert_eq!(doc.status(), Some(500));
+
+    let registry = registry();
+    assert_eq!(
+        registry.map_errors(&doc),
+        vec![AppError::Other(403), AppError::Other(422), AppError::Other(500)]
+    );
+
+    // Round trip through an error document
+    let original = vec![
+        AppError::Forbidden(Some("No dogs allowed".into())),
+        AppError::NotFound,
+    ];
+    let doc = DocumentError::from_errors(vec![
+        AppError::Forbidden(Some("No dogs allowed".into())),
+        AppError::NotFound,
+    ]);
+    assert_eq!(registry.map_errors(&doc), original);
+}
+
+#[test]
+fn it_turns_documents_into_results() {
+    let _ = env_logger::try_init();
+    let registry = registry();
+
+    let doc = JsonApiDocument::from_str(
+        r#"{ "data": { "type": "dogs", "id": "1", "attributes": { "name": "Fido" } } }"#,
+    ).unwrap();
+    let dog: Result<Dog, AppError> = registry.into_result(&doc);
+    assert_eq!(
+        dog,
+        Ok(Dog {
+            id: "1".into(),
+            name: "Fido".into(),
+        })
+    );
+
+    let doc = JsonApiDocument::from_str(
+        r#"{ "errors": [ { "status": "404", "code": "not_found" }, { "status": "403" } ] }"#,
+    ).unwrap();
+    let dog: Result<Dog, AppError> = registry.into_result(&doc);
+    assert_eq!(dog, Err(AppError::NotFound));
+
+    let doc = JsonApiDocument::from_str(
+        r#"{ "data": { "type": "dogs", "id": "1", "attributes": {} } }"#,
+    ).unwrap();
+    let dog: Result<Dog, AppError> = registry.into_result(&doc);
+    assert_eq!(dog, Err(AppError::Conversion));
+}
+
+#[test]
+fn request_validation_errors_are_jsonapi_errors() {
+    let _ = env_logger::try_init();
+    let doc = DocumentError::from_errors(vec![
+        RequestValidationError::MissingType,
+        RequestValidationError::TypeMismatch("dogs".into(), "cats".into()),
+    ]);
+    assert_eq!(doc.errors[0].status, Some("400".into()));
+    assert_eq!(doc.errors[1].status, Some("409".into()));
+    assert_eq!(doc.status(), Some(400));
+}
+
+#[test]
+fn it_builds_errors() {
+    let _ = env_logger::try_init();
+    let error = JsonApiError::builder(HttpStatus::BAD_REQUEST)
+        .id("1")
+        .code("bad_header")
+        .header("X-Api-Version")
+        .about("http://example.com/errors/bad_header")
+        .type_link("http://example.com/errors/header")
+        .meta("version", serde_json::Value::from(2))
+        .build();
+
+    assert_eq!(error.id, Some("1".into()));
+    assert_eq!(error.status, Some("400".into()));
+    assert_eq!(error.title, Some("Bad Request".into()));
+    let source = error.source.clone().unwrap();
+    assert_eq!(source.header, Some("X-Api-Version".into()));
+    assert_eq!(source.pointer, None);
+    assert_eq!(
+        error.type_link(),
+        Some(&serde_json::Value::from("http://example.com/errors/header"))
+    );
+    assert_eq!(
+        error.links.unwrap()["about"],
+        serde_json::Value::from("http://example.com/errors/bad_header")
+    );
+    assert_eq!(error.meta.unwrap()["version"], serde_json::Value::from(2));
+
+    let error: JsonApiError = JsonApiError::builder(HttpStatus::new(418).unwrap())
+        .title("Teapot")
+        .into();
+    assert_eq!(error.status, Some("418".into()));
+    assert_eq!(error.title, Some("Teapot".into()));
+
+    assert_eq!(HttpStatus::new(200), None);
+    assert_eq!(HttpStatus::new(600), None);
+    assert_eq!(HttpStatus::new(418).unwrap().canonical_reason(), None);
+}
+
+#[test]
+fn it_constructs_common_error_documents() {
+    let _ = env_logger::try_init();
+    let doc = DocumentError::not_found("dogs", "7");
+    assert_eq!(doc.status(), Some(404));
+    assert_eq!(doc.errors[0].title, Some("Not Found".into()));
+    assert_eq!(
+        doc.errors[0].detail,
+        Some("Resource of type 'dogs' with id '7' not found".into())
+    );
+
+    let doc = DocumentError::validation_failed(vec![("name", "Name can't be blank")]);
+    assert_eq!(doc.status(), Some(422));
+    assert_eq!(
+        doc.errors[0].source.as_ref().unwrap().pointer,
+        Some("/data/attributes/name".into())
+    );
+
+    let doc = DocumentError::conflict("Name already taken");
+    assert_eq!(doc.status(), Some(409));
+    assert_eq!(doc.errors[0].detail, Some("Name already taken".into()));
+
+    let doc = DocumentError::unsupported_include("owner.friends");
+    assert_eq!(doc.status(), Some(400));
+    let source = doc.errors[0].source.as_ref().unwrap();
+    assert_eq!(source.parameter, Some("include".into()));
+    assert_eq!(source.pointer, None);
+}
+
+fn parse_error_pointer<T: FromStr<Err = errors::Error> + std::fmt::Debug>(s: &str) -> Option<String> {
+    let doc: DocumentError = T::from_str(s).unwrap_err().into();
+    assert_eq!(doc.status(), Some(400));
+    doc.errors[0].source.as_ref().and_then(|source| source.pointer.clone())
+}
+
+#[test]
+fn it_points_at_the_member_that_failed_to_parse() {
+    let _ = env_logger::try_init();
+    assert_eq!(
+        parse_error_pointer::<JsonApiDocument>(
+            r#"{ "data": { "id": "1", "type": "dogs", "attributes": {}, "links": [] } }"#
+        ),
+        Some("/data/links".into())
+    );
+    assert_eq!(
+        parse_error_pointer::<JsonApiDocument>(
+            r#"{ "data": [], "included": [ { "id": "1", "type": "dogs" }, { "type": "dogs", "id": 2 } ] }"#
+        ),
+        Some("/included/1/id".into())
+    );
+    assert_eq!(
+        parse_error_pointer::<JsonApiDocument>(r#"{ "errors": [ { "status": 404 } ] }"#),
+        Some("/errors/0/status".into())
+    );
+    assert_eq!(
+        parse_error_pointer::<Resource>(
+            r#"{ "id": "1", "type": "dogs", "relationships": { "owner": { "links": 1 } } }"#
+        ),
+        Some("/relationships/owner/links".into())
+    );
+    assert_eq!(
+        parse_error_pointer::<NewResourceDocument>(r#"{ "data": { "type": 7, "attributes": {} } }"#),
+        Some("/data/type".into())
+    );
+    // Syntax errors have no member to point at
+    assert_eq!(parse_error_pointer::<JsonApiDocument>(r#"{ "data": "#), None);
+}
+
+#[test]
+fn parse_errors_become_error_documents() {
+    let _ = env_logger::try_init();
+    let error = JsonApiDocument::from_str(r#"{ "data": { "type": "dogs", "id": "1", "attributes": 7 } }"#)
+        .unwrap_err();
+    match error {
+        errors::Error::Parse { pointer: Some(ref pointer), .. } => assert_eq!(pointer, "/data/attributes"),
+        _ => assert!(false),
+    }
+
+    let doc = DocumentError::from(error);
+    assert_eq!(doc.errors.len(), 1);
+    assert_eq!(doc.errors[0].status, Some("400".into()));
+    assert_eq!(doc.errors[0].title, Some("Invalid document".into()));
+    assert!(doc.errors[0].detail.is_some());
+
+    let request: serde_json::Value =
+        serde_json::from_str(r#"{ "data": { "type": "dogs", "id": "1", "attributes": 7 } }"#).unwrap();
+    assert!(doc.validate_sources(&request).is_none());
+}
+
+fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+#[test]
+fn errors_are_structured() {
+    let _ = env_logger::try_init();
+    assert_send_sync::<errors::Error>();
+
+    let resource = Resource::from_str(r#"{ "type": "dogs", "id": "1", "attributes": { "name": 7 } }"#)
+        .unwrap();
+    match resource.into_model::<Dog>() {
+        Err(errors::Error::Conversion { resource_type, id, field, .. }) => {
+            assert_eq!(resource_type, Some("dogs".into()));
+            assert_eq!(id, Some("1".into()));
+            assert_eq!(field, Some("name".into()));
+        }
+        _ => assert!(false),
+    }
+
+    let error = errors::Error::from(vec![
+        RequestValidationError::MissingType,
+        RequestValidationError::IdMismatch("1".into(), "2".into()),
+    ]);
+    assert_eq!(error.status(), 400);
+    let doc = DocumentError::from(error);
+    assert_eq!(doc.errors.len(), 2);
+    assert_eq!(doc.errors[1].status, Some("409".into()));
+    assert_eq!(
+        doc.errors[1].source.as_ref().unwrap().pointer,
+        Some("/data/id".into())
+    );
+
+    let doc = DocumentError::from(errors::Error::from(vec![
+        DocumentValidationError::IncludedWithoutData,
+        DocumentValidationError::MissingContent,
+    ]));
+    assert_eq!(doc.errors.len(), 2);
+    assert_eq!(doc.status(), Some(400));
+
+    let doc = DocumentError::from(errors::Error::from(vec![LinkageValidationError::MissingId]));
+    assert_eq!(doc.errors[0].source.as_ref().unwrap().pointer, Some("/data".into()));
+
+    let error = jsonapi::query::Query::try_from_params("page[size]=x").unwrap_err();
+    assert_eq!(error.status(), 400);
+    assert_eq!(
+        error.error_source().and_then(|source| source.parameter),
+        Some("page[size]".into())
+    );
+    // With `std::error::Error` in scope too, `source` is unambiguous
+    assert!(error.source().is_none());
+
+    let error = errors::Error::from(DiffPatchError::NonExistentProperty("title".into()));
+    assert_eq!(error.status(), 409);
+    assert_eq!(error.to_string(), "Error applying patch: NonExistentProperty(\"title\")");
+    assert_eq!(errors::Error::from(DiffPatchError::NotADataDocument).status(), 400);
+}
@@ -1,5 +1,6 @@
 //! The purpose of these tests is to validate compliance with the JSONAPI
 //! specification and to ensure that this crate reads documents properly
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
 extern crate jsonapi;
 extern crate serde_json;
 extern crate env_logger;
@@ -332,7 +333,7 @@ fn can_deserialize_jsonapi_example_resource_003() {
 #[test]
 fn can_deserialize_jsonapi_example_resource_004() {
     let _ = env_logger::try_init();
-    let s = ::read_json_file("data/resource_004.json");
+    let s = crate::read_json_file("data/resource_004.json");
     let data: Result<Resource, serde_json::Error> = serde_json::from_str(&s);
     assert!(data.is_ok());
 }
@@ -457,7 +458,10 @@ fn can_diff_resource() {
                         }
                         Ok(patchset) => {
                             println!("can_diff_resource: PatchSet is {:?}", patchset);
-                            assert_eq!(patchset.patches.len(), 5);
+                            // Five attributes and the `author` relationship
+                            assert_eq!(patchset.patches.len(), 6);
+                            assert_eq!(patchset.patches[5].patch_type, PatchType::Relationship);
+                            assert_eq!(patchset.patches[5].operation, PatchOperation::Replace);
                         }
                     }
                 }
@@ -518,6 +522,90 @@ fn it_omits_empty_error_keys() {
     );
 }
 
+#[test]
+fn it_omits_empty_error_source_members() {
+    let _ = env_logger::try_init();
+    let source = ErrorSource {
+        header: Some("Accept".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(serde_json::to_string(&source).unwrap(), r#"{"header":"Accept"}"#);
+
+    let parsed: ErrorSource = serde_json::from_str(r#"{"header":"Accept"}"#).unwrap();
+    assert_eq!(parsed, source);
+}
+
+#[test]
+fn it_reads_error_links() {
+    let _ = env_logger::try_init();
+    let error: JsonApiError = serde_json::from_str(
+        r#"{
+            "status": "409",
+            "links": {
+                "about": "http://example.com/errors/1",
+                "type": "http://example.com/errors/conflict"
+            }
+        }"#,
+    ).unwrap();
+    assert_eq!(
+        error.type_link(),
+        Some(&JsonApiValue::from("http://example.com/errors/conflict"))
+    );
+    assert_eq!(
+        error.about_link(),
+        Some(&JsonApiValue::from("http://example.com/errors/1"))
+    );
+    assert_eq!(JsonApiError::default().type_link(), None);
+}
+
+#[test]
+fn it_validates_error_source_pointers() {
+    let _ = env_logger::try_init();
+    let request: JsonApiValue = serde_json::from_str(
+        r#"{ "data": { "type": "posts", "attributes": { "a/b": 1, "tags": ["x"] } } }"#,
+    ).unwrap();
+    let pointer = |p: &str| ErrorSource {
+        pointer: Some(p.to_string()),
+        ..Default::default()
+    };
+
+    assert!(pointer("").validate(&request).is_none());
+    assert!(pointer("/data/attributes/a~1b").validate(&request).is_none());
+    assert!(pointer("/data/attributes/tags/0").validate(&request).is_none());
+    assert!(ErrorSource::default().validate(&request).is_none());
+    assert_eq!(
+        pointer("data/type").validate(&request),
+        Some(vec![ErrorSourceValidationError::InvalidPointer("data/type".into())])
+    );
+    assert_eq!(
+        pointer("/data/attributes/a~2b").validate(&request),
+        Some(vec![ErrorSourceValidationError::InvalidPointer("/data/attributes/a~2b".into())])
+    );
+    assert_eq!(
+        pointer("/data/id").validate(&request),
+        Some(vec![ErrorSourceValidationError::UnresolvedPointer("/data/id".into())])
+    );
+
+    let doc = DocumentError {
+        errors: vec![
+            JsonApiError {
+                source: Some(pointer("/data/type")),
+                ..Default::default()
+            },
+            JsonApiError::default(),
+            JsonApiError {
+                source: Some(pointer("/data/attributes/title")),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        doc.validate_sources(&request),
+        Some(vec![ErrorSourceValidationError::UnresolvedPointer("/data/attributes/title".into())])
+    );
+}
+
 #[test]
 fn it_allows_for_optional_attributes() {
     let _ = env_logger::try_init();
@@ -610,3 +698,782 @@ fn it_validates_partialeq_when_compariing_documents() {
     assert_eq!(doc2.is_ok(), true);
     assert!(doc1.unwrap() == doc2.unwrap());
 }
+
+#[test]
+fn can_deserialize_relationship_documents() {
+    let _ = env_logger::try_init();
+    let s = crate::read_json_file("data/relationship_tags.json");
+    let data: Result<RelationshipDocument, serde_json::Error> = serde_json::from_str(&s);
+    match data {
+        Err(_) => assert!(false),
+        Ok(doc) => {
+            assert!(doc.is_valid());
+            assert!(doc.links.is_some());
+            match doc.data {
+                IdentifierData::Multiple(ref identifiers) => assert_eq!(identifiers.len(), 2),
+                _ => assert!(false),
+            }
+        }
+    }
+
+    let to_one: RelationshipDocument =
+        serde_json::from_str(r#"{"data": {"type": "people", "id": "12"}}"#).unwrap();
+    assert_eq!(
+        to_one.data,
+        IdentifierData::Single(ResourceIdentifier {
+            _type: "people".into(),
+            id: "12".into(),
+        })
+    );
+
+    let cleared: RelationshipDocument = serde_json::from_str(r#"{"data": null}"#).unwrap();
+    assert_eq!(cleared.data, IdentifierData::None);
+    assert_eq!(serde_json::to_string(&cleared).unwrap(), r#"{"data":null}"#);
+
+    let missing: Result<RelationshipDocument, serde_json::Error> = serde_json::from_str(r#"{}"#);
+    assert!(missing.is_err());
+}
+
+#[test]
+fn it_validates_relationship_documents() {
+    let _ = env_logger::try_init();
+    let doc: RelationshipDocument = serde_json::from_str(
+        r#"{"data": [
+            {"type": "tags", "id": "2"},
+            {"type": "tags", "id": "2"},
+            {"type": "", "id": "3"}
+        ]}"#,
+    ).unwrap();
+
+    match doc.validate() {
+        None => assert!(false),
+        Some(errors) => {
+            assert!(errors.contains(&LinkageValidationError::DuplicateIdentifier));
+            assert!(errors.contains(&LinkageValidationError::MissingType));
+            assert!(!errors.contains(&LinkageValidationError::MissingId));
+        }
+    }
+}
+
+fn tag(id: &str) -> ResourceIdentifier {
+    ResourceIdentifier {
+        _type: "tags".into(),
+        id: id.into(),
+    }
+}
+
+#[test]
+fn it_computes_relationship_changes() {
+    let _ = env_logger::try_init();
+    let current = Relationship {
+        data: Some(IdentifierData::Multiple(vec![tag("1"), tag("2")])),
+        links: None,
+    };
+    let doc = RelationshipDocument::new(IdentifierData::Multiple(vec![tag("2"), tag("3")]));
+
+    let replaced = doc.changes(RelationshipOperation::Replace, Some(&current)).unwrap();
+    assert_eq!(replaced.added, vec![tag("3")]);
+    assert_eq!(replaced.removed, vec![tag("1")]);
+
+    let added = doc.changes(RelationshipOperation::Add, Some(&current)).unwrap();
+    assert_eq!(added.added, vec![tag("3")]);
+    assert!(added.removed.is_empty());
+
+    let removed = doc.changes(RelationshipOperation::Remove, Some(&current)).unwrap();
+    assert!(removed.added.is_empty());
+    assert_eq!(removed.removed, vec![tag("2")]);
+
+    let added_to_nothing = doc.changes(RelationshipOperation::Add, None).unwrap();
+    assert_eq!(added_to_nothing.added, vec![tag("2"), tag("3")]);
+}
+
+#[test]
+fn it_computes_to_one_relationship_changes() {
+    let _ = env_logger::try_init();
+    let current = Relationship {
+        data: Some(IdentifierData::Single(tag("1"))),
+        links: None,
+    };
+
+    let swap = RelationshipDocument::new(IdentifierData::Single(tag("2")));
+    let changes = swap.changes(RelationshipOperation::Replace, Some(&current)).unwrap();
+    assert_eq!(changes.added, vec![tag("2")]);
+    assert_eq!(changes.removed, vec![tag("1")]);
+
+    let clear = RelationshipDocument::new(IdentifierData::None);
+    let changes = clear.changes(RelationshipOperation::Replace, Some(&current)).unwrap();
+    assert!(changes.added.is_empty());
+    assert_eq!(changes.removed, vec![tag("1")]);
+
+    let many = RelationshipDocument::new(IdentifierData::Multiple(vec![tag("2")]));
+    assert_eq!(
+        many.changes(RelationshipOperation::Replace, Some(&current)),
+        Err(RelationshipAssumptionError::RelationshipIsAList)
+    );
+    assert_eq!(
+        many.changes(RelationshipOperation::Add, Some(&current)),
+        Err(RelationshipAssumptionError::RelationshipIsNotAList)
+    );
+    assert_eq!(
+        swap.changes(RelationshipOperation::Remove, None),
+        Err(RelationshipAssumptionError::RelationshipIsNotAList)
+    );
+}
+
+#[test]
+fn it_parses_create_requests_without_an_id() {
+    let _ = env_logger::try_init();
+    let serialized = r#"{
+        "data": {
+            "type": "photos",
+            "attributes": {
+                "title": "Ember Hamster",
+                "src": "http://example.com/images/productivity.png"
+            },
+            "relationships": {
+                "photographer": {
+                    "data": { "type": "people", "id": "9" }
+                }
+            }
+        }
+    }"#;
+
+    // A create request can't be read as a `Resource` as it has no `id`
+    let as_document: Result<DocumentData, serde_json::Error> = serde_json::from_str(serialized);
+    assert!(as_document.is_err());
+
+    let doc: NewResourceDocument = serde_json::from_str(serialized).unwrap();
+    assert_eq!(doc.data.id, None);
+    assert!(doc.data.validate("photos", false).is_none());
+
+    let resource = doc.data.into_resource("42".into());
+    assert_eq!(resource.id, "42");
+    assert_eq!(resource._type, "photos");
+    assert!(resource.get_relationship("photographer").is_some());
+}
+
+#[test]
+fn it_validates_create_requests() {
+    let _ = env_logger::try_init();
+    let new_resource = NewResource {
+        _type: "photos".into(),
+        id: Some("550e8400-e29b-41d4-a716-446655440000".into()),
+        ..Default::default()
+    };
+
+    assert!(new_resource.validate("photos", true).is_none());
+
+    match new_resource.validate("articles", false) {
+        None => assert!(false),
+        Some(errors) => {
+            assert_eq!(
+                errors,
+                vec![
+                    RequestValidationError::TypeMismatch("articles".into(), "photos".into()),
+                    RequestValidationError::ClientIdForbidden,
+                ]
+            );
+            assert_eq!(errors[0].status(), 409);
+            assert_eq!(errors[1].status(), 403);
+
+            let doc = DocumentError::from(&errors[..]);
+            assert_eq!(doc.errors.len(), 2);
+            assert_eq!(doc.errors[0].status, Some("409".into()));
+            assert_eq!(
+                doc.errors[1].source.as_ref().and_then(|s| s.pointer.clone()),
+                Some("/data/id".into())
+            );
+        }
+    }
+
+    let untyped = NewResource::default();
+    match untyped.validate("photos", false) {
+        None => assert!(false),
+        Some(errors) => {
+            assert_eq!(errors, vec![RequestValidationError::MissingType]);
+            assert_eq!(errors[0].status(), 400);
+        }
+    }
+
+    // A request that omits `type` is reported by `validate`, not by serde
+    let doc: NewResourceDocument = serde_json::from_str(r#"{"data": {"attributes": {"title": "Ember Hamster"}}}"#).unwrap();
+    assert_eq!(
+        doc.data.validate("photos", false),
+        Some(vec![RequestValidationError::MissingType])
+    );
+}
+
+#[test]
+fn it_validates_update_requests() {
+    let _ = env_logger::try_init();
+    let doc: ResourceUpdateDocument = serde_json::from_str(
+        r#"{"data": {"type": "articles", "id": "1", "attributes": {"title": "To TDD or Not"}}}"#,
+    ).unwrap();
+
+    assert!(doc.data.validate("articles", "1").is_none());
+
+    match doc.data.validate("articles", "2") {
+        None => assert!(false),
+        Some(errors) => {
+            assert_eq!(
+                errors,
+                vec![RequestValidationError::IdMismatch("2".into(), "1".into())]
+            );
+            assert_eq!(errors[0].to_jsonapi_error().status, Some("409".into()));
+        }
+    }
+
+    let resource: Resource = doc.data.into();
+    assert_eq!(resource.attributes.len(), 1);
+
+    // Requests that omit `type` or `id` are reported by `validate`, not by
+    // serde
+    let missing_id: ResourceUpdateDocument = serde_json::from_str(r#"{"data": {"type": "articles"}}"#).unwrap();
+    assert_eq!(
+        missing_id.data.validate("articles", "1"),
+        Some(vec![RequestValidationError::MissingId])
+    );
+    let missing_both: ResourceUpdateDocument = serde_json::from_str(r#"{"data": {"attributes": {}}}"#).unwrap();
+    match missing_both.data.validate("articles", "1") {
+        None => assert!(false),
+        Some(errors) => {
+            assert_eq!(
+                errors,
+                vec![RequestValidationError::MissingType, RequestValidationError::MissingId]
+            );
+            let doc = DocumentError::from(&errors[..]);
+            assert_eq!(doc.errors[0].status, Some("400".into()));
+            assert_eq!(
+                doc.errors[1].source.as_ref().and_then(|s| s.pointer.clone()),
+                Some("/data/id".into())
+            );
+        }
+    }
+}
+
+#[test]
+fn can_diff_added_and_removed_members() {
+    let _ = env_logger::try_init();
+    let before: Resource = serde_json::from_str(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 250 },
+        "relationships": {
+            "author": { "data": { "type": "people", "id": "9" } },
+            "tags": { "data": [ { "type": "tags", "id": "1" }, { "type": "tags", "id": "2" } ] }
+        },
+        "meta": { "version": 1 }
+    }"#).unwrap();
+    let after: Resource = serde_json::from_str(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "score": 5 },
+        "relationships": {
+            "tags": { "data": [ { "type": "tags", "id": "2" }, { "type": "tags", "id": "3" } ] },
+            "editor": { "data": null }
+        },
+        "links": { "self": "http://example.com/posts/1" },
+        "meta": { "version": 2 }
+    }"#).unwrap();
+
+    let patchset = before.diff(after.clone()).unwrap();
+    let summary: Vec<(PatchType, PatchOperation, &str)> = patchset
+        .patches
+        .iter()
+        .map(|p| (p.patch_type, p.operation, p.subject.as_str()))
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            (PatchType::Attribute, PatchOperation::Remove, "likes"),
+            (PatchType::Attribute, PatchOperation::Add, "score"),
+            (PatchType::Relationship, PatchOperation::Remove, "author"),
+            (PatchType::Relationship, PatchOperation::Add, "editor"),
+            (PatchType::RelationshipMember, PatchOperation::Remove, "tags"),
+            (PatchType::RelationshipMember, PatchOperation::Add, "tags"),
+            (PatchType::Link, PatchOperation::Add, "self"),
+            (PatchType::Meta, PatchOperation::Replace, "version"),
+        ]
+    );
+    assert_eq!(patchset.patches[4].previous, serde_json::json!({"type": "tags", "id": "1"}));
+    assert_eq!(patchset.patches[4].index, Some(0));
+    assert_eq!(patchset.patches[5].next, serde_json::json!({"type": "tags", "id": "3"}));
+    assert_eq!(patchset.patches[5].index, Some(1));
+
+    let patched = before.patch(&patchset).unwrap();
+    assert_eq!(patched, after);
+}
+
+#[test]
+fn can_diff_reordered_relationship_members() {
+    let _ = env_logger::try_init();
+    let tags = |ids: &[&str]| -> Resource {
+        let data: Vec<serde_json::Value> = ids
+            .iter()
+            .map(|id| serde_json::json!({ "type": "tags", "id": id }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "relationships": { "tags": { "data": data } }
+        }))
+        .unwrap()
+    };
+
+    let before = tags(&["1", "2"]);
+    let after = tags(&["2", "1"]);
+    assert_ne!(before, after);
+
+    // Order matters, so a reorder replaces the relationship as a whole
+    let patchset = before.diff(after.clone()).unwrap();
+    assert_eq!(patchset.patches.len(), 1);
+    assert_eq!(patchset.patches[0].patch_type, PatchType::Relationship);
+    assert_eq!(patchset.patches[0].operation, PatchOperation::Replace);
+    assert_eq!(before.patch(&patchset).unwrap(), after);
+
+    // As does a reorder combined with added members
+    let patchset = before.diff(tags(&["3", "2", "1"])).unwrap();
+    assert_eq!(patchset.patches.len(), 1);
+    assert_eq!(patchset.patches[0].patch_type, PatchType::Relationship);
+}
+
+#[test]
+fn can_round_trip_relationship_members() {
+    let _ = env_logger::try_init();
+    let tags = |ids: &[&str]| -> Resource {
+        let data: Vec<serde_json::Value> = ids
+            .iter()
+            .map(|id| serde_json::json!({ "type": "tags", "id": id }))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "type": "posts",
+            "id": "1",
+            "relationships": { "tags": { "data": data } }
+        }))
+        .unwrap()
+    };
+
+    for (from, to) in &[
+        (vec!["1", "2"], vec!["1", "3", "2"]),
+        (vec!["1", "2", "3", "4"], vec!["5", "2", "6", "4", "7"]),
+        (vec!["1", "2", "3"], vec!["2"]),
+        (vec![], vec!["1", "2"]),
+        (vec!["1", "2", "3"], vec!["3", "1", "2"]),
+        (vec!["1", "2", "3"], vec!["4", "3", "2"]),
+    ] {
+        let before = tags(from);
+        let after = tags(to);
+        let patchset = before.diff(after.clone()).unwrap();
+        assert_eq!(before.patch(&patchset).unwrap(), after, "{:?} -> {:?}", from, to);
+        assert_eq!(after.patch(&patchset.invert()).unwrap(), before, "{:?} -> {:?}", from, to);
+    }
+
+    // Inserted members go where the patch says
+    let before = tags(&["1", "2"]);
+    let patchset = before.diff(tags(&["1", "3", "2"])).unwrap();
+    assert_eq!(patchset.patches.len(), 1);
+    assert_eq!(patchset.patches[0].index, Some(1));
+    assert_eq!(
+        tags(&[]).patch(&patchset),
+        Err(DiffPatchError::NonExistentProperty("tags".into()))
+    );
+
+    // A member that has moved since the diff is a conflict
+    let patchset = before.diff(tags(&["2"])).unwrap();
+    assert_eq!(
+        tags(&["2", "1"]).patch(&patchset),
+        Err(DiffPatchError::IncorrectPropertyValue("tags".into()))
+    );
+}
+
+#[test]
+fn can_diff_to_one_relationship_swap() {
+    let _ = env_logger::try_init();
+    let before: Resource = serde_json::from_str(
+        r#"{"type": "posts", "id": "1", "relationships": {"author": {"data": {"type": "people", "id": "9"}}}}"#
+    ).unwrap();
+    let after: Resource = serde_json::from_str(
+        r#"{"type": "posts", "id": "1", "relationships": {"author": {"data": {"type": "people", "id": "10"}}}}"#
+    ).unwrap();
+
+    let patchset = before.diff(after).unwrap();
+    assert_eq!(patchset.patches.len(), 1);
+    assert_eq!(patchset.patches[0].patch_type, PatchType::Relationship);
+    assert_eq!(patchset.patches[0].operation, PatchOperation::Replace);
+    assert_eq!(
+        patchset.patches[0].previous,
+        serde_json::json!({"data": {"type": "people", "id": "9"}})
+    );
+    assert_eq!(
+        patchset.patches[0].next,
+        serde_json::json!({"data": {"type": "people", "id": "10"}})
+    );
+
+    let other_type: Resource = serde_json::from_str(r#"{"type": "people", "id": "1"}"#).unwrap();
+    assert_eq!(
+        before.diff(other_type).unwrap_err(),
+        DiffPatchError::IncompatibleTypes("posts".into(), "people".into())
+    );
+}
+
+#[test]
+fn it_detects_conflicts_when_applying_patches() {
+    let _ = env_logger::try_init();
+    let before: Resource = serde_json::from_str(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 250 },
+        "relationships": {
+            "tags": { "data": [ { "type": "tags", "id": "1" } ] }
+        }
+    }"#).unwrap();
+    let after: Resource = serde_json::from_str(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 251, "score": 5 },
+        "relationships": {
+            "tags": { "data": [ { "type": "tags", "id": "1" }, { "type": "tags", "id": "2" } ] }
+        },
+        "meta": { "version": 2 }
+    }"#).unwrap();
+    let patchset = before.diff(after.clone()).unwrap();
+
+    // Someone else changed `likes` in the meantime
+    let mut concurrent = before.clone();
+    concurrent.attributes.insert("likes".into(), serde_json::json!(300));
+    assert_eq!(
+        concurrent.apply(&patchset),
+        Err(DiffPatchError::IncorrectPropertyValue("likes".into()))
+    );
+    // A failed apply leaves the resource untouched
+    assert_eq!(concurrent.attributes.get("likes"), Some(&serde_json::json!(300)));
+    assert_eq!(concurrent.attributes.get("score"), None);
+
+    // ... or removed it altogether
+    let mut removed = before.clone();
+    removed.attributes.remove("likes");
+    assert_eq!(
+        removed.apply(&patchset),
+        Err(DiffPatchError::NonExistentProperty("likes".into()))
+    );
+
+    // The member being added to `tags` is already there
+    let mut tagged = before.clone();
+    tagged.relationships = after.relationships.clone();
+    assert_eq!(
+        tagged.apply(&patchset),
+        Err(DiffPatchError::IncorrectPropertyValue("tags".into()))
+    );
+
+    // The patchset targets another resource
+    let mut other: Resource = serde_json::from_str(r#"{"type": "posts", "id": "2"}"#).unwrap();
+    assert_eq!(
+        other.apply(&patchset),
+        Err(DiffPatchError::IncorrectPropertyValue("id".into()))
+    );
+
+    let mut resource = before.clone();
+    resource.apply(&patchset).unwrap();
+    assert_eq!(resource, after);
+
+    resource.apply(&patchset.invert()).unwrap();
+    assert_eq!(resource, before);
+}
+
+#[test]
+fn it_inverts_patchsets() {
+    let _ = env_logger::try_init();
+    let before: Resource = serde_json::from_str(
+        r#"{"type": "posts", "id": "1", "attributes": {"likes": 250}}"#
+    ).unwrap();
+    let after: Resource = serde_json::from_str(
+        r#"{"type": "posts", "id": "1", "attributes": {"score": 5}}"#
+    ).unwrap();
+
+    let patchset = before.diff(after.clone()).unwrap();
+    let inverted = patchset.invert();
+
+    assert_eq!(inverted.patches.len(), 2);
+    assert_eq!(inverted.patches[0].operation, PatchOperation::Remove);
+    assert_eq!(inverted.patches[0].subject, "score");
+    assert_eq!(inverted.patches[0].previous, serde_json::json!(5));
+    assert_eq!(inverted.patches[1].operation, PatchOperation::Add);
+    assert_eq!(inverted.patches[1].subject, "likes");
+    assert_eq!(inverted.patches[1].next, serde_json::json!(250));
+
+    assert_eq!(inverted.invert(), patchset);
+    assert_eq!(after.patch(&inverted).unwrap(), before);
+}
+
+#[test]
+fn can_diff_nested_attributes() {
+    let _ = env_logger::try_init();
+    let before: Resource = serde_json::from_str(r#"{
+        "type": "people",
+        "id": "1",
+        "attributes": {
+            "name": "Dan",
+            "address": { "street": "Main St", "city": "Amsterdam", "geo": { "lat": 52.37 } },
+            "tags": ["a", "b", "c"],
+            "phones": [ { "kind": "home", "number": "123" } ]
+        }
+    }"#).unwrap();
+    let after: Resource = serde_json::from_str(r#"{
+        "type": "people",
+        "id": "1",
+        "attributes": {
+            "name": "Dan",
+            "address": { "city": "Utrecht", "zip": "3511", "geo": { "lat": 52.09 } },
+            "tags": ["a"],
+            "phones": [ { "kind": "home", "number": "456" }, { "kind": "work", "number": "789" } ]
+        }
+    }"#).unwrap();
+
+    // A shallow diff replaces each changed attribute as a whole
+    assert_eq!(before.diff(after.clone()).unwrap().patches.len(), 3);
+
+    let patchset = before.diff_deep(after.clone()).unwrap();
+    let summary: Vec<(PatchOperation, &str)> = patchset
+        .patches
+        .iter()
+        .map(|p| (p.operation, p.subject.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (PatchOperation::Replace, "/address/city"),
+            (PatchOperation::Replace, "/address/geo/lat"),
+            (PatchOperation::Remove, "/address/street"),
+            (PatchOperation::Add, "/address/zip"),
+            (PatchOperation::Replace, "/phones/0/number"),
+            (PatchOperation::Add, "/phones/1"),
+            (PatchOperation::Remove, "/tags/2"),
+            (PatchOperation::Remove, "/tags/1"),
+        ]
+    );
+
+    let patched = before.patch(&patchset).unwrap();
+    assert_eq!(patched, after);
+    assert_eq!(after.patch(&patchset.invert()).unwrap(), before);
+
+    // Nested patches are checked against the current value too
+    let mut moved = before.clone();
+    moved.attributes.insert("address".into(), serde_json::json!({ "city": "Rotterdam" }));
+    assert_eq!(
+        moved.apply(&patchset),
+        Err(DiffPatchError::IncorrectPropertyValue("/address/city".into()))
+    );
+    let mut moved_out = before.clone();
+    moved_out.attributes.remove("address");
+    assert_eq!(
+        moved_out.apply(&patchset),
+        Err(DiffPatchError::NonExistentProperty("/address/city".into()))
+    );
+}
+
+#[test]
+fn can_diff_names_containing_separators() {
+    let _ = env_logger::try_init();
+    let before: Resource = serde_json::from_str(r#"{
+        "type": "people",
+        "id": "1",
+        "attributes": {
+            "first.name": "Dan",
+            "h": { "a.b": 1, "c/d": [1], "e~f": { "g": true } }
+        }
+    }"#).unwrap();
+    let after: Resource = serde_json::from_str(r#"{
+        "type": "people",
+        "id": "1",
+        "attributes": {
+            "first.name": "Daniel",
+            "h": { "a.b": 2, "c/d": [1, 2], "e~f": { "g": false } }
+        }
+    }"#).unwrap();
+
+    let patchset = before.diff(after.clone()).unwrap();
+    assert_eq!(patchset.patches[0].subject, "first.name");
+    assert_eq!(before.patch(&patchset).unwrap(), after);
+    assert_eq!(after.patch(&patchset.invert()).unwrap(), before);
+
+    let patchset = before.diff_deep(after.clone()).unwrap();
+    let subjects: Vec<&str> = patchset.patches.iter().map(|p| p.subject.as_str()).collect();
+    assert_eq!(subjects, vec!["first.name", "/h/a.b", "/h/c~1d/1", "/h/e~0f/g"]);
+    assert_eq!(before.patch(&patchset).unwrap(), after);
+    assert_eq!(after.patch(&patchset.invert()).unwrap(), before);
+}
+
+#[test]
+fn can_diff_documents() {
+    let _ = env_logger::try_init();
+    let s = crate::read_json_file("data/compound_document.json");
+    let previous: JsonApiDocument = serde_json::from_str(&s).unwrap();
+
+    let mut next = previous.clone();
+    if let JsonApiDocument::Data(ref mut doc) = next {
+        if let Some(PrimaryData::Multiple(ref mut resources)) = doc.data {
+            resources[0].attributes.insert("title".into(), serde_json::json!("JSON:API paints my shed!"));
+            resources.push(Resource {
+                _type: "articles".into(),
+                id: "2".into(),
+                ..Default::default()
+            });
+        }
+        if let Some(ref mut included) = doc.included {
+            included.remove(0);
+        }
+    }
+
+    assert!(previous.diff(&previous).unwrap().is_empty());
+
+    let diff = previous.diff(&next).unwrap();
+    assert_eq!(diff.data.created.len(), 1);
+    assert_eq!(diff.data.created[0].id, "2");
+    assert!(diff.data.deleted.is_empty());
+    assert_eq!(diff.data.modified.len(), 1);
+    assert_eq!(diff.data.modified[0].resource_id, "1");
+    assert_eq!(diff.data.modified[0].patches[0].subject, "title");
+
+    assert!(diff.included.created.is_empty());
+    assert_eq!(diff.included.deleted.len(), 1);
+    assert_eq!(diff.included.deleted[0]._type, "people");
+    assert!(diff.included.modified.is_empty());
+
+    let error = JsonApiDocument::Error(DocumentError::default());
+    assert_eq!(previous.diff(&error), Err(DiffPatchError::NotADataDocument));
+}
+
+#[test]
+fn can_diff_resource_collections() {
+    let _ = env_logger::try_init();
+    let previous: Resources = serde_json::from_str(r#"[
+        { "type": "posts", "id": "1", "attributes": { "title": "One" } },
+        { "type": "people", "id": "1", "attributes": { "name": "Dan" } },
+        { "type": "posts", "id": "2", "attributes": { "title": "Two" } }
+    ]"#).unwrap();
+    let next: Resources = serde_json::from_str(r#"[
+        { "type": "people", "id": "1", "attributes": { "name": "Daniel" } },
+        { "type": "posts", "id": "2", "attributes": { "title": "Two" } },
+        { "type": "people", "id": "2", "attributes": { "name": "Anna" } },
+        { "type": "people", "id": "2", "attributes": { "name": "Anna" } }
+    ]"#).unwrap();
+
+    let diff = ResourcesDiff::between(&previous, &next).unwrap();
+    assert_eq!(diff.created.len(), 1);
+    assert_eq!((diff.created[0]._type.as_str(), diff.created[0].id.as_str()), ("people", "2"));
+    assert_eq!(diff.deleted.len(), 1);
+    assert_eq!((diff.deleted[0]._type.as_str(), diff.deleted[0].id.as_str()), ("posts", "1"));
+    assert_eq!(diff.modified.len(), 1);
+    assert_eq!(diff.modified[0].resource_type, "people");
+    assert_eq!(diff.modified[0].patches[0].next, serde_json::json!("Daniel"));
+}
+
+#[test]
+fn can_get_typed_attributes() {
+    let _ = env_logger::try_init();
+    let s = crate::read_json_file("data/resource_all_attributes.json");
+    let res: Resource = serde_json::from_str(&s).unwrap();
+
+    assert_eq!(res.get_attribute_as::<i64>("likes"), Ok(250));
+    assert_eq!(res.get_attribute_as::<String>("title"), Ok("Rails is Omakase".into()));
+    assert_eq!(res.get_attribute_as::<bool>("published"), Ok(true));
+    assert_eq!(
+        res.get_attribute_as::<Vec<String>>("tags"),
+        Ok(vec!["rails".to_string(), "news".to_string()])
+    );
+    assert_eq!(
+        res.get_attribute_as::<bool>("likes"),
+        Err(JsonApiDataError::IncompatibleAttributeType)
+    );
+    assert_eq!(
+        res.get_attribute_as::<bool>("nonexistent"),
+        Err(JsonApiDataError::AttributeNotFound)
+    );
+
+    assert_eq!(res.get_attribute_pointer("/tags/1"), Some(&serde_json::json!("news")));
+    assert_eq!(res.get_attribute_pointer_as::<String>("/tags/0"), Ok("rails".into()));
+    assert_eq!(
+        res.get_attribute_pointer_as::<String>("/tags/5"),
+        Err(JsonApiDataError::AttributeNotFound)
+    );
+    assert_eq!(res.get_attribute_pointer("tags"), None);
+}
+
+#[test]
+fn can_set_attributes() {
+    let _ = env_logger::try_init();
+    let mut res: Resource = serde_json::from_str(r#"{
+        "type": "people",
+        "id": "1",
+        "attributes": { "address": { "city": "Amsterdam" }, "phones": [] },
+        "relationships": { "employer": { "data": null } }
+    }"#).unwrap();
+
+    assert_eq!(res.set_attribute("name", "Dan"), Ok(None));
+    assert_eq!(res.set_attribute("name", "Daniel"), Ok(Some(serde_json::json!("Dan"))));
+
+    for reserved in &["id", "type", "employer"] {
+        assert_eq!(
+            res.set_attribute(reserved, 1),
+            Err(JsonApiDataError::ReservedAttributeName)
+        );
+    }
+    assert_eq!(
+        res.set_attribute("profile", serde_json::json!({ "links": { "self": "/" } })),
+        Err(JsonApiDataError::ReservedAttributeName)
+    );
+
+    assert_eq!(
+        res.set_attribute_pointer("/address/city", "Utrecht"),
+        Ok(Some(serde_json::json!("Amsterdam")))
+    );
+    assert_eq!(res.set_attribute_pointer("/address/zip", "3511"), Ok(None));
+    assert_eq!(res.set_attribute_pointer("/phones/-", "123"), Ok(None));
+    assert_eq!(res.set_attribute_pointer("/phones/0", "456"), Ok(Some(serde_json::json!("123"))));
+    assert_eq!(
+        res.get_attribute_as::<serde_json::Value>("address"),
+        Ok(serde_json::json!({ "city": "Utrecht", "zip": "3511" }))
+    );
+
+    assert_eq!(
+        res.set_attribute_pointer("/address/links", "x"),
+        Err(JsonApiDataError::ReservedAttributeName)
+    );
+    assert_eq!(
+        res.set_attribute_pointer("/nothing/here", 1),
+        Err(JsonApiDataError::AttributeNotFound)
+    );
+    assert_eq!(
+        res.set_attribute_pointer("/address/city/name", 1),
+        Err(JsonApiDataError::IncompatibleAttributeType)
+    );
+}
+
+#[test]
+fn it_reads_pagination_links() {
+    let _ = env_logger::try_init();
+    let s = crate::read_json_file("data/pagination.json");
+    let doc: DocumentData = serde_json::from_str(&s).unwrap();
+    let pagination = doc.pagination();
+    assert_eq!(
+        pagination.next,
+        Some("http://example.com/articles?page[number]=4&page[size]=1".into())
+    );
+    assert_eq!(
+        pagination.last,
+        Some("http://example.com/articles?page[number]=13&page[size]=1".into())
+    );
+
+    // Link objects and missing links
+    let doc: DocumentData = serde_json::from_str(
+        r#"{ "data": [], "links": { "next": { "href": "/articles?page[number]=2" }, "prev": null } }"#,
+    ).unwrap();
+    assert_eq!(
+        doc.pagination(),
+        Pagination {
+            next: Some("/articles?page[number]=2".into()),
+            ..Default::default()
+        }
+    );
+}
@@ -0,0 +1,231 @@
+#![cfg(feature = "axum")]
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate axum;
+extern crate tokio;
+extern crate tower;
+extern crate http_body_util;
+extern crate env_logger;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::routing::{get, post};
+use axum::{Extension, Router};
+use http_body_util::BodyExt;
+use jsonapi::api::*;
+use jsonapi::media_type::Negotiator;
+use jsonapi::model::*;
+use jsonapi::web::axum::*;
+use tower::ServiceExt;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Dog {
+    id: String,
+    name: String,
+}
+jsonapi_model!(Dog; "dogs");
+
+const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+async fn rename(JsonApi(mut dog): JsonApi<Dog>) -> JsonApi<Dog> {
+    dog.name = dog.name.to_uppercase();
+    JsonApi(dog)
+}
+
+async fn echo(JsonApi(doc): JsonApi<JsonApiDocument>) -> JsonApiDocument {
+    doc
+}
+
+async fn negotiated(media_type: JsonApiMediaType, JsonApi(doc): JsonApi<JsonApiDocument>) -> (JsonApiMediaType, JsonApiDocument) {
+    (media_type, doc)
+}
+
+async fn page_size(JsonApiQuery(query): JsonApiQuery) -> String {
+    query.page.map(|page| page.size).unwrap_or_default().to_string()
+}
+
+async fn missing() -> Result<JsonApiDocument, DocumentError> {
+    Err(DocumentError::not_found("dogs", "7"))
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/dogs", post(rename).get(page_size))
+        .route("/echo", post(echo))
+        .route("/negotiated", post(negotiated))
+        .route("/dogs/7", get(missing))
+}
+
+fn post_request(uri: &str, content_type: &str, body: &str) -> Request<Body> {
+    Request::post(uri)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+async fn send(app: Router, request: Request<Body>) -> (StatusCode, Option<String>, serde_json::Value) {
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, content_type, body)
+}
+
+const DOG: &str = r#"{ "data": { "type": "dogs", "id": "1", "attributes": { "name": "rex" } } }"#;
+
+const DECLARED: &str = r#"{
+    "jsonapi": { "version": "1.1", "ext": ["https://jsonapi.org/ext/atomic"] },
+    "data": { "type": "dogs", "id": "1", "attributes": { "name": "Rex" } }
+}"#;
+
+#[tokio::test]
+async fn it_extracts_and_responds_with_models() {
+    let _ = env_logger::try_init();
+    let (status, content_type, body) = send(app(), post_request("/dogs", MEDIA_TYPE, DOG)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["data"]["attributes"]["name"], "REX");
+    assert_eq!(body["data"]["type"], "dogs");
+}
+
+#[tokio::test]
+async fn it_extracts_documents() {
+    let _ = env_logger::try_init();
+    let (status, _, body) = send(app(), post_request("/echo", MEDIA_TYPE, DOG)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, serde_json::from_str::<serde_json::Value>(DOG).unwrap());
+
+    // Documents must be valid
+    let (status, _, body) = send(app(), post_request("/echo", MEDIA_TYPE, r#"{ "included": [] }"#)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errors"].as_array().map(Vec::len), Some(2));
+}
+
+#[tokio::test]
+async fn it_rejects_invalid_bodies_with_error_documents() {
+    let _ = env_logger::try_init();
+    let (status, content_type, body) = send(
+        app(),
+        post_request("/dogs", MEDIA_TYPE, r#"{ "data": { "type": "dogs", "id": 1 } }"#),
+    ).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["errors"][0]["source"]["pointer"], "/data/id");
+
+    let (status, _, body) = send(
+        app(),
+        post_request("/dogs", MEDIA_TYPE, r#"{ "data": { "type": "dogs", "id": "1", "attributes": {} } }"#),
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["status"], "422");
+}
+
+#[tokio::test]
+async fn it_negotiates_media_types() {
+    let _ = env_logger::try_init();
+    let (status, _, body) = send(app(), post_request("/dogs", "application/json", DOG)).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body["errors"][0]["source"]["header"], "Content-Type");
+
+    let request = Request::post("/dogs")
+        .header(header::CONTENT_TYPE, MEDIA_TYPE)
+        .header(header::ACCEPT, "application/vnd.api+json; charset=utf-8")
+        .body(Body::from(DOG))
+        .unwrap();
+    let (status, _, _) = send(app(), request).await;
+    assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+
+    // Extensions are supported once a negotiator declares them
+    let ext = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#;
+    let (status, _, _) = send(app(), post_request("/dogs", ext, DOG)).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let negotiator = Negotiator::new().support_extension("https://jsonapi.org/ext/atomic");
+    let (status, _, _) = send(app().layer(Extension(negotiator)), post_request("/dogs", ext, DOG)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn it_responds_with_the_negotiated_media_type() {
+    let _ = env_logger::try_init();
+    let ext = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#;
+    let negotiator = Negotiator::new()
+        .support_extension("https://jsonapi.org/ext/atomic")
+        .support_profile("https://example.com/profile");
+    let request = Request::post("/negotiated")
+        .header(header::CONTENT_TYPE, MEDIA_TYPE)
+        .header(
+            header::ACCEPT,
+            r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"; profile="https://example.com/profile https://example.com/other""#,
+        )
+        .body(Body::from(DOG))
+        .unwrap();
+    let (status, content_type, body) = send(app().layer(Extension(negotiator.clone())), request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        content_type.as_deref(),
+        Some(r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"; profile="https://example.com/profile""#)
+    );
+    assert_eq!(body["data"]["id"], "1");
+
+    // Without an `Accept` header the media type has no parameters
+    let (_, content_type, _) = send(app().layer(Extension(negotiator)), post_request("/negotiated", ext, DOG)).await;
+    assert_eq!(content_type.as_deref(), Some(MEDIA_TYPE));
+}
+
+#[tokio::test]
+async fn it_advertises_declared_extensions() {
+    let _ = env_logger::try_init();
+    let (status, content_type, _) = send(app(), post_request("/echo", MEDIA_TYPE, DECLARED)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        content_type.as_deref(),
+        Some(r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#)
+    );
+
+    // The negotiated media type keeps them
+    let negotiator = Negotiator::new().support_profile("https://example.com/profile");
+    let request = Request::post("/negotiated")
+        .header(header::CONTENT_TYPE, MEDIA_TYPE)
+        .header(header::ACCEPT, r#"application/vnd.api+json; profile="https://example.com/profile""#)
+        .body(Body::from(DECLARED))
+        .unwrap();
+    let (_, content_type, _) = send(app().layer(Extension(negotiator)), request).await;
+    assert_eq!(
+        content_type.as_deref(),
+        Some(r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"; profile="https://example.com/profile""#)
+    );
+}
+
+#[tokio::test]
+async fn it_extracts_queries() {
+    let _ = env_logger::try_init();
+    let request = Request::get("/dogs?page[size]=5").body(Body::empty()).unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&bytes[..], b"5");
+
+    let request = Request::get("/dogs?page[size]=five").body(Body::empty()).unwrap();
+    let (status, _, body) = send(app(), request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errors"][0]["source"]["parameter"], "page[size]");
+}
+
+#[tokio::test]
+async fn it_responds_with_error_documents() {
+    let _ = env_logger::try_init();
+    let request = Request::get("/dogs/7").body(Body::empty()).unwrap();
+    let (status, content_type, body) = send(app(), request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(content_type, Some(MEDIA_TYPE.to_string()));
+    assert_eq!(body["errors"][0]["title"], "Not Found");
+}
@@ -0,0 +1,164 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+extern crate jsonapi;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::borrowed::*;
+use jsonapi::errors::Error;
+use std::borrow::Cow;
+
+mod helper;
+use crate::helper::read_json_file;
+
+#[test]
+fn it_converts_to_owned_documents() {
+    let _ = env_logger::try_init();
+    for file in &[
+        "data/compound_document.json",
+        "data/collection.json",
+        "data/pagination.json",
+        "data/resource_001.json",
+        "data/links_002.json",
+        "data/jsonapi_info_001.json",
+    ] {
+        let s = read_json_file(file);
+        let owned: DocumentData = serde_json::from_str(&s).unwrap();
+        let borrowed = DocumentRef::from_str(&s).unwrap();
+        assert_eq!(borrowed.to_document().unwrap(), owned, "{}", file);
+        assert_eq!(borrowed.primary_resources().len(), owned.primary_resources().len());
+
+        // Serializing writes the raw members back unchanged
+        let reserialized = serde_json::to_string(&borrowed).unwrap();
+        assert_eq!(serde_json::from_str::<DocumentData>(&reserialized).unwrap(), owned);
+    }
+}
+
+#[test]
+fn it_orders_members_like_owned_documents() {
+    let _ = env_logger::try_init();
+    let s = r#"{
+      "data": {
+        "type": "articles",
+        "id": "1",
+        "attributes": { "z": 1, "m": 2, "a": 3, "q": 4 },
+        "relationships": {
+          "tags": { "data": [] },
+          "author": { "data": null },
+          "comments": { "data": [] }
+        }
+      }
+    }"#;
+    let owned: DocumentData = serde_json::from_str(s).unwrap();
+    let owned = &owned.primary_resources()[0];
+    let borrowed = DocumentRef::from_str(s).unwrap();
+    let converted = borrowed.to_document().unwrap();
+    let converted = &converted.primary_resources()[0];
+
+    let names = |resource: &Resource| -> (Vec<String>, Vec<String>) {
+        (
+            resource.attributes.keys().cloned().collect(),
+            resource.relationships.as_ref().unwrap().keys().cloned().collect(),
+        )
+    };
+    assert_eq!(names(converted), names(owned));
+    let borrowed_names: Vec<&str> = borrowed.primary_resources()[0].attributes.keys().map(|key| key.as_ref()).collect();
+    if cfg!(feature = "preserve_order") {
+        assert_eq!(borrowed_names, vec!["z", "m", "a", "q"]);
+        assert_eq!(names(converted).1, vec!["tags", "author", "comments"]);
+    } else {
+        assert_eq!(borrowed_names, vec!["a", "m", "q", "z"]);
+    }
+}
+
+#[test]
+fn it_borrows_from_the_input() {
+    let _ = env_logger::try_init();
+    let s = r#"{
+      "data": {
+        "type": "articles",
+        "id": "1\u00e9",
+        "attributes": { "title": "JSON:API", "escaped\ttitle": "a\"b", "words": 3 },
+        "relationships": {
+          "author": { "data": { "type": "people", "id": "9" }, "links": { "self": "/articles/1/author" } },
+          "tags": { "data": [] }
+        }
+      }
+    }"#;
+    let doc = DocumentRef::from_str(s).unwrap();
+    let resource = &doc.primary_resources()[0];
+    assert!(matches!(resource._type, Cow::Borrowed("articles")));
+    // Escape sequences require a copy
+    assert!(matches!(resource.id, Cow::Owned(ref id) if id == "1é"));
+    assert!(resource
+        .attributes
+        .keys()
+        .all(|key| matches!(key, Cow::Borrowed(_)) == (key != "escaped\ttitle")));
+
+    assert_eq!(resource.attribute::<&str>("title").unwrap(), Some("JSON:API"));
+    assert_eq!(resource.attribute::<String>("escaped\ttitle").unwrap(), Some("a\"b".into()));
+    assert_eq!(resource.attribute::<u32>("words").unwrap(), Some(3));
+    assert_eq!(resource.attribute::<u32>("missing").unwrap(), None);
+    match resource.attribute::<u32>("title") {
+        Err(Error::Parse { pointer, .. }) => assert_eq!(pointer, Some("/attributes/title".into())),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let author = resource.relationship("author").unwrap();
+    assert_eq!(
+        author.data,
+        Some(IdentifierDataRef::Single(ResourceIdentifierRef {
+            _type: "people".into(),
+            id: "9".into(),
+        }))
+    );
+    assert_eq!(
+        author.data.as_ref().unwrap().to_identifier_data(),
+        IdentifierData::Single(ResourceIdentifier {
+            _type: "people".into(),
+            id: "9".into(),
+        })
+    );
+    assert_eq!(resource.relationship("tags").unwrap().data, Some(IdentifierDataRef::Multiple(vec![])));
+
+    let owned = resource.to_resource().unwrap();
+    assert_eq!(owned.attributes["escaped\ttitle"], "a\"b");
+    assert_eq!(
+        owned.relationships.unwrap()["author"].links.as_ref().unwrap()["self"],
+        "/articles/1/author"
+    );
+}
+
+#[test]
+fn it_reads_empty_primary_data() {
+    let _ = env_logger::try_init();
+    let s = r#"{ "data": null, "meta": { "total": 0 } }"#;
+    let doc = DocumentRef::from_str(s).unwrap();
+    assert!(doc.primary_resources().is_empty());
+    assert_eq!(doc.to_document().unwrap(), serde_json::from_str::<DocumentData>(s).unwrap());
+
+    let doc = DocumentRef::from_str(r#"{ "meta": { "total": 0 } }"#).unwrap();
+    assert!(doc.data.is_none());
+    assert_eq!(doc.to_document().unwrap().meta.unwrap()["total"], 0);
+}
+
+#[test]
+fn it_reports_invalid_documents() {
+    let _ = env_logger::try_init();
+    match DocumentRef::from_str(r#"{ "data": [{ "type": "a" }] }"#) {
+        Err(Error::Parse { pointer, detail }) => {
+            assert_eq!(pointer, None);
+            assert!(detail.contains("missing field `id`"));
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert!(DocumentRef::from_str(r#"{ "data": 1 }"#).is_err());
+
+    // Members are only checked once they are converted
+    let s = r#"{ "data": [{ "type": "a", "id": "1" }, { "type": "a", "id": "2", "links": [] }] }"#;
+    let doc = DocumentRef::from_str(s).unwrap();
+    match doc.to_document() {
+        Err(Error::Parse { pointer, .. }) => assert_eq!(pointer, Some("/data/1/links".into())),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
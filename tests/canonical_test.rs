@@ -0,0 +1,132 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::canonical::*;
+use jsonapi::errors::Error;
+use jsonapi::model::*;
+use std::io;
+use std::str::FromStr;
+
+mod helper;
+use crate::helper::read_json_file;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Flea {
+    id: String,
+    name: String,
+    age: u32,
+    colour: String,
+}
+jsonapi_model!(Flea; "fleas");
+
+#[test]
+fn it_writes_equal_documents_identically() {
+    let _ = env_logger::try_init();
+    let a = r#"{
+      "data": { "type": "articles", "id": "1", "attributes": { "title": "A", "tags": ["z", "a"], "author": { "name": "Dan", "age": 30 } } },
+      "links": { "self": "/articles/1", "related": { "href": "/x", "meta": { "b": 1, "a": 2 } } },
+      "meta": { "z": null, "a": 1.5 }
+    }"#;
+    let b = r#"{
+      "meta": { "a": 1.5, "z": null },
+      "links": { "related": { "meta": { "a": 2, "b": 1 }, "href": "/x" }, "self": "/articles/1" },
+      "data": { "attributes": { "author": { "age": 30, "name": "Dan" }, "tags": ["z", "a"], "title": "A" }, "id": "1", "type": "articles" }
+    }"#;
+    let a = JsonApiDocument::from_str(a).unwrap();
+    let b = JsonApiDocument::from_str(b).unwrap();
+    assert_eq!(a, b);
+
+    let canonical = to_canonical_string(&a).unwrap();
+    assert_eq!(canonical, to_canonical_string(&b).unwrap());
+    assert_eq!(
+        canonical,
+        concat!(
+            r#"{"data":{"attributes":{"author":{"age":30,"name":"Dan"},"tags":["z","a"],"title":"A"},"id":"1","type":"articles"},"#,
+            r#""links":{"related":{"href":"/x","meta":{"a":2,"b":1}},"self":"/articles/1"},"meta":{"a":1.5,"z":null}}"#
+        )
+    );
+    assert_eq!(to_canonical_vec(&b).unwrap(), canonical.as_bytes());
+}
+
+#[test]
+fn it_round_trips_canonical_output() {
+    let _ = env_logger::try_init();
+    for file in &[
+        "data/compound_document.json",
+        "data/collection.json",
+        "data/errors.json",
+        "data/pagination.json",
+        "data/resource_all_attributes.json",
+    ] {
+        let doc = JsonApiDocument::from_str(&read_json_file(file)).unwrap();
+        let canonical = to_canonical_string(&doc).unwrap();
+        let reparsed = JsonApiDocument::from_str(&canonical).unwrap();
+        assert_eq!(reparsed, doc, "{}", file);
+        assert_eq!(to_canonical_string(&reparsed).unwrap(), canonical, "{}", file);
+    }
+
+    let flea = Flea {
+        id: "1".into(),
+        name: "Mr.Flea".into(),
+        age: 2,
+        colour: "brown".into(),
+    };
+    assert_eq!(
+        to_canonical_string(&flea.to_jsonapi_resource().0).unwrap(),
+        r#"{"attributes":{"age":2,"colour":"brown","name":"Mr.Flea"},"id":"1","type":"fleas"}"#
+    );
+}
+
+struct FailingWriter;
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn it_reports_write_failures() {
+    let _ = env_logger::try_init();
+    let doc = JsonApiDocument::from_str(&read_json_file("data/collection.json")).unwrap();
+    match to_canonical_writer(FailingWriter, &doc) {
+        Err(Error::Io(err)) => assert_eq!(err.to_string(), "disk full"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn it_preserves_insertion_order() {
+    let _ = env_logger::try_init();
+    let flea = Flea {
+        id: "1".into(),
+        name: "Mr.Flea".into(),
+        age: 2,
+        colour: "brown".into(),
+    };
+    let (resource, _) = flea.to_jsonapi_resource();
+    let names: Vec<&str> = resource.attributes.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["name", "age", "colour"]);
+
+    let mut meta = Meta::new();
+    meta.insert("z".into(), 1.into());
+    meta.insert("a".into(), 2.into());
+    assert_eq!(serde_json::to_string(&meta).unwrap(), r#"{"z":1,"a":2}"#);
+    assert_eq!(to_canonical_string(&meta).unwrap(), r#"{"a":2,"z":1}"#);
+
+    let s = r#"{ "data": { "type": "a", "id": "1", "attributes": { "z": 1, "m": 2, "a": 3 } } }"#;
+    let doc: DocumentData = serde_json::from_str(s).unwrap();
+    let names: Vec<&str> = doc.primary_resources()[0].attributes.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["z", "m", "a"]);
+}
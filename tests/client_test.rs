@@ -0,0 +1,308 @@
+#![cfg(feature = "client")]
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::client::*;
+use jsonapi::errors::Error;
+use jsonapi::model::*;
+use jsonapi::query::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Owner {
+    id: String,
+    name: String,
+}
+jsonapi_model!(Owner; "people");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Dog {
+    id: String,
+    name: String,
+    owner: Owner,
+}
+jsonapi_model!(Dog; "dogs"; has one owner);
+
+const BASE_URL: &str = "https://example.com/api";
+
+fn dog(id: &str, name: &str) -> String {
+    format!(
+        r#"{{ "type": "dogs", "id": "{}", "attributes": {{ "name": "{}" }},
+             "relationships": {{ "owner": {{ "data": {{ "type": "people", "id": "1" }} }} }} }}"#,
+        id, name
+    )
+}
+
+const OWNER: &str = r#"{ "type": "people", "id": "1", "attributes": { "name": "Dan" } }"#;
+
+fn page(dogs: &[String], next: Option<&str>) -> String {
+    format!(
+        r#"{{ "data": [{}], "included": [{}], "links": {{ "next": {} }} }}"#,
+        dogs.join(","),
+        OWNER,
+        next.map(|next| format!(r#""{}""#, next)).unwrap_or_else(|| "null".into())
+    )
+}
+
+#[test]
+fn it_builds_urls() {
+    let _ = env_logger::try_init();
+    let client = Client::new("https://example.com/api/", MockTransport::new());
+    assert_eq!(client.url("/dogs", None), "https://example.com/api/dogs");
+    assert_eq!(client.url("dogs/1", None), "https://example.com/api/dogs/1");
+
+    let query = Query {
+        include: Some(vec!["owner".into()]),
+        page: Some(PageParams { size: 2, number: 1 }),
+        ..Default::default()
+    };
+    assert_eq!(
+        client.url("/dogs", Some(&query)),
+        "https://example.com/api/dogs?include=owner&page[size]=2&page[number]=1"
+    );
+    assert_eq!(
+        client.url("/dogs?sort=name", Some(&Query::default())),
+        "https://example.com/api/dogs?sort=name"
+    );
+}
+
+#[test]
+fn it_fetches_models_with_included_resources() {
+    let _ = env_logger::try_init();
+    let url = format!("{}/dogs/1?include=owner", BASE_URL);
+    let body = format!(r#"{{ "data": {}, "included": [{}] }}"#, dog("1", "Rex"), OWNER);
+    let client = Client::new(BASE_URL, MockTransport::new().respond(Method::Get, &url, 200, &body))
+        .header("Authorization", "Bearer token");
+
+    let query = Query {
+        include: Some(vec!["owner".into()]),
+        ..Default::default()
+    };
+    let rex: Dog = client.get("/dogs/1", Some(&query)).unwrap();
+    assert_eq!(rex.name, "Rex");
+    assert_eq!(rex.owner.name, "Dan");
+
+    let requests = client.transport().requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].header("accept"), Some("application/vnd.api+json"));
+    assert_eq!(requests[0].header("Authorization"), Some("Bearer token"));
+    assert_eq!(requests[0].header("Content-Type"), None);
+
+    // A collection is not a single resource
+    let client = Client::new(
+        BASE_URL,
+        MockTransport::new().respond(Method::Get, &format!("{}/dogs", BASE_URL), 200, &page(&[], None)),
+    );
+    assert!(client.list::<Dog>("/dogs", None).unwrap().is_empty());
+    match client.get::<Dog>("/dogs", None) {
+        Err(ClientError::Decode(Error::Parse { pointer, .. })) => assert_eq!(pointer, Some("/data".into())),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn it_follows_next_links() {
+    let _ = env_logger::try_init();
+    let transport = MockTransport::new()
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs?page[size]=2",
+            200,
+            // Links are resolved against the URL of the page, not the base URL
+            &page(&[dog("1", "Rex"), dog("2", "Fido")], Some("/api/dogs?page[number]=2&page[size]=2")),
+        )
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs?page[number]=2&page[size]=2",
+            200,
+            &page(&[dog("3", "Lassie")], Some("https://example.com/api/dogs?page[number]=3&page[size]=2")),
+        )
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs?page[number]=3&page[size]=2",
+            200,
+            &page(&[dog("4", "Laika")], Some("?page[number]=4&page[size]=2")),
+        )
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs?page[number]=4&page[size]=2",
+            200,
+            &page(&[dog("5", "Hachiko")], Some("dogs?page[number]=5&page[size]=2")),
+        )
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs?page[number]=5&page[size]=2",
+            200,
+            &page(&[], None),
+        );
+    let client = Client::new(BASE_URL, transport);
+
+    let dogs: Vec<Dog> = client
+        .stream("/dogs?page[size]=2", None)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let names: Vec<&str> = dogs.iter().map(|dog| dog.name.as_str()).collect();
+    assert_eq!(names, vec!["Rex", "Fido", "Lassie", "Laika", "Hachiko"]);
+    assert!(dogs.iter().all(|dog| dog.owner.name == "Dan"));
+    assert_eq!(client.transport().requests().len(), 5);
+
+    let pages: Vec<_> = client.pages("/dogs?page[size]=2", None).collect();
+    assert_eq!(pages.len(), 5);
+    assert_eq!(pages[0].as_ref().unwrap().primary_resources().len(), 2);
+}
+
+#[test]
+fn it_stops_streaming_on_errors() {
+    let _ = env_logger::try_init();
+    let transport = MockTransport::new()
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs",
+            200,
+            &page(&[dog("1", "Rex")], Some("?page[number]=2")),
+        )
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs?page[number]=2",
+            503,
+            r#"{ "errors": [{ "status": "503", "title": "Service Unavailable", "detail": "Try again later" }] }"#,
+        );
+    let client = Client::new(BASE_URL, transport);
+
+    let mut stream = client.stream::<Dog>("/dogs", None);
+    assert_eq!(stream.next().unwrap().unwrap().name, "Rex");
+    let error = stream.next().unwrap().unwrap_err();
+    assert_eq!(error.status(), Some(503));
+    assert_eq!(error.errors().unwrap().errors[0].detail, Some("Try again later".into()));
+    assert_eq!(error.to_string(), "Server responded with 503: Try again later");
+    assert!(stream.next().is_none());
+
+    // Links that can't be resolved are an error
+    let transport = MockTransport::new().respond(
+        Method::Get,
+        "https://example.com/api/dogs",
+        200,
+        &page(&[dog("1", "Rex")], Some("https://[invalid")),
+    );
+    let client = Client::new(BASE_URL, transport);
+    let mut pages = client.pages("/dogs", None);
+    assert!(pages.next().unwrap().is_ok());
+    match pages.next() {
+        Some(Err(ClientError::Decode(Error::Parse { pointer, .. }))) => assert_eq!(pointer, Some("/links/next".into())),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert!(pages.next().is_none());
+}
+
+#[test]
+fn it_sends_models() {
+    let _ = env_logger::try_init();
+    let created = format!(r#"{{ "data": {}, "included": [{}] }}"#, dog("7", "Rex"), OWNER);
+    let transport = MockTransport::new()
+        .respond(Method::Post, "https://example.com/api/dogs", 201, &created)
+        .respond(Method::Patch, "https://example.com/api/dogs/7", 200, &created)
+        .respond(Method::Delete, "https://example.com/api/dogs/7", 204, "");
+    let client = Client::new(BASE_URL, transport);
+
+    let rex = Dog {
+        id: "7".into(),
+        name: "Rex".into(),
+        owner: Owner {
+            id: "1".into(),
+            name: "Dan".into(),
+        },
+    };
+    assert_eq!(client.create("/dogs", &rex).unwrap(), Some(rex.clone()));
+    assert_eq!(client.update("/dogs/7", &rex).unwrap(), Some(rex));
+    assert!(client.delete("/dogs/7").is_ok());
+
+    let requests = client.transport().requests();
+    assert_eq!(requests[0].method, Method::Post);
+    assert_eq!(requests[0].header("Content-Type"), Some("application/vnd.api+json"));
+    let body: serde_json::Value = serde_json::from_slice(requests[0].body.as_ref().unwrap()).unwrap();
+    assert_eq!(body["data"]["attributes"]["name"], "Rex");
+    assert_eq!(body["data"]["relationships"]["owner"]["data"]["id"], "1");
+    // Request documents carry the primary resource only
+    assert_eq!(body.get("included"), None);
+    let update: ResourceUpdateDocument = serde_json::from_slice(requests[1].body.as_ref().unwrap()).unwrap();
+    assert_eq!(update.data.id, "7");
+    assert_eq!(update.data.attributes["name"], "Rex");
+    let body: serde_json::Value = serde_json::from_slice(requests[1].body.as_ref().unwrap()).unwrap();
+    assert_eq!(body.get("included"), None);
+    assert_eq!(requests[2].body, None);
+}
+
+#[test]
+fn it_accepts_responses_without_content() {
+    let _ = env_logger::try_init();
+    let transport = MockTransport::new()
+        .respond(Method::Post, "https://example.com/api/dogs", 204, "")
+        .respond(Method::Patch, "https://example.com/api/dogs/7", 204, "")
+        .respond(Method::Patch, "https://example.com/api/dogs/8", 200, "not json");
+    let client = Client::new(BASE_URL, transport);
+
+    let rex = Dog {
+        id: "7".into(),
+        name: "Rex".into(),
+        owner: Owner {
+            id: "1".into(),
+            name: "Dan".into(),
+        },
+    };
+    assert_eq!(client.create("/dogs", &rex).unwrap(), None);
+    assert_eq!(client.update("/dogs/7", &rex).unwrap(), None);
+    // A body that is not a document is still an error
+    match client.update("/dogs/8", &rex) {
+        Err(ClientError::Decode(_)) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn it_surfaces_typed_errors() {
+    let _ = env_logger::try_init();
+    let transport = MockTransport::new()
+        .respond(Method::Get, "https://example.com/api/dogs/1", 502, "<html>Bad Gateway</html>")
+        .respond(Method::Get, "https://example.com/api/dogs/2", 200, "[]")
+        .respond(
+            Method::Get,
+            "https://example.com/api/dogs/3",
+            200,
+            r#"{ "data": { "type": "dogs", "id": "3", "attributes": {} } }"#,
+        );
+    let client = Client::new(BASE_URL, transport);
+
+    match client.get::<Dog>("/dogs/1", None) {
+        Err(ClientError::Status { status, body }) => {
+            assert_eq!(status, 502);
+            assert_eq!(body, "<html>Bad Gateway</html>");
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match client.get::<Dog>("/dogs/2", None) {
+        Err(ClientError::Decode(Error::Parse { .. })) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match client.get::<Dog>("/dogs/3", None) {
+        Err(ClientError::Decode(Error::Conversion { id, .. })) => assert_eq!(id, Some("3".into())),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match client.get::<Dog>("/dogs/4", None) {
+        Err(ClientError::Transport(err)) => {
+            assert_eq!(err.to_string(), "No response for GET https://example.com/api/dogs/4")
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    // Closures are transports too
+    let offline = Client::new(BASE_URL, |_: Request| -> Result<Response, TransportError> {
+        Err(TransportError("Connection refused".into()))
+    });
+    let error = offline.fetch("/dogs", None).unwrap_err();
+    assert_eq!(error.status(), None);
+    assert_eq!(error.to_string(), "Request failed: Connection refused");
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "etag")]
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+extern crate jsonapi;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::canonical::{to_canonical_string, to_canonical_vec};
+use jsonapi::etag::*;
+use std::str::FromStr;
+
+mod helper;
+use crate::helper::read_json_file;
+
+const REX: &str = "13f964ede82cfe97922abfe69d3d170f7323beb82ca81e80163772c1615f499b";
+
+#[test]
+fn it_hashes_content_regardless_of_member_order() {
+    let _ = env_logger::try_init();
+    let a = Resource::from_str(r#"{ "type": "dogs", "id": "1", "attributes": { "name": "Rex", "tags": ["b", "a"] } }"#).unwrap();
+    let b = Resource::from_str(r#"{ "attributes": { "tags": ["b", "a"], "name": "Rex" }, "id": "1", "type": "dogs" }"#).unwrap();
+    assert_eq!(a.content_hash(), b.content_hash());
+    assert_eq!(a.content_hash(), content_hash(&a).unwrap());
+    // The hash is stable across runs and versions
+    assert_eq!(a.content_hash().to_string(), REX);
+    // Served bytes depend on the serialization, so the default tag is weak
+    assert_eq!(a.etag().to_string(), format!("W/\"{}\"", REX));
+    assert_eq!(a.content_hash().strong_etag().to_string(), format!("\"{}\"", REX));
+
+    // Unless the body is the canonical serialization
+    let (body, etag) = canonical_body(&b).unwrap();
+    assert_eq!(body, to_canonical_vec(&a).unwrap());
+    assert_eq!(etag.to_string(), format!("\"{}\"", REX));
+
+    // Array order is significant
+    let c = Resource::from_str(r#"{ "type": "dogs", "id": "1", "attributes": { "name": "Rex", "tags": ["a", "b"] } }"#).unwrap();
+    assert_ne!(a.content_hash(), c.content_hash());
+
+    for file in &["data/compound_document.json", "data/collection.json", "data/errors.json"] {
+        let doc = JsonApiDocument::from_str(&read_json_file(file)).unwrap();
+        let reparsed = JsonApiDocument::from_str(&to_canonical_string(&doc).unwrap()).unwrap();
+        assert_eq!(doc.etag(), reparsed.etag(), "{}", file);
+        assert!(doc.etag().weak_eq(&reparsed.etag()));
+        assert!(!doc.etag().strong_eq(&reparsed.etag()));
+    }
+    let collection = JsonApiDocument::from_str(&read_json_file("data/collection.json")).unwrap();
+    let compound = JsonApiDocument::from_str(&read_json_file("data/compound_document.json")).unwrap();
+    assert_ne!(collection.content_hash(), compound.content_hash());
+}
+
+#[test]
+fn it_parses_and_compares_entity_tags() {
+    let _ = env_logger::try_init();
+    let strong = ETag::from_str(r#""xyzzy""#).unwrap();
+    let weak = ETag::from_str(r#" W/"xyzzy" "#).unwrap();
+    assert_eq!(strong, ETag::strong("xyzzy").unwrap());
+    assert_eq!(weak, ETag::weak("xyzzy").unwrap());
+    assert_eq!(weak.tag(), "xyzzy");
+    assert!(weak.is_weak());
+    assert_eq!(weak.to_string(), r#"W/"xyzzy""#);
+    assert_eq!(ETag::from_str(r#""""#).unwrap().tag(), "");
+
+    assert!(strong.strong_eq(&ETag::strong("xyzzy").unwrap()));
+    assert!(!strong.strong_eq(&weak));
+    assert!(!weak.strong_eq(&weak));
+    assert!(strong.weak_eq(&weak));
+    assert!(weak.weak_eq(&weak));
+    assert!(!strong.weak_eq(&ETag::strong("other").unwrap()));
+
+    for invalid in &["xyzzy", r#""xyzzy"#, r#"w/"xyzzy""#, r#""a b""#, r#""a" "b""#, "*"] {
+        assert_eq!(ETag::from_str(invalid), Err(InvalidETag(invalid.to_string())), "{}", invalid);
+    }
+    assert_eq!(
+        InvalidETag("xyzzy".into()).to_string(),
+        "Invalid entity tag: xyzzy"
+    );
+}
+
+#[test]
+fn it_evaluates_preconditions() {
+    let _ = env_logger::try_init();
+    let current = ETag::strong("v2").unwrap();
+    let weak = ETag::weak("v2").unwrap();
+
+    // `false` means `304 Not Modified` for GET
+    assert!(!if_none_match(r#""v2""#, Some(&current)));
+    assert!(!if_none_match(r#""v1", W/"v2""#, Some(&current)));
+    assert!(!if_none_match(r#""v2""#, Some(&weak)));
+    assert!(!if_none_match("*", Some(&current)));
+    assert!(if_none_match(r#""v1""#, Some(&current)));
+    assert!(if_none_match("*", None));
+    assert!(if_none_match(r#""v2""#, None));
+    assert!(if_none_match(r#""v2" garbage"#, Some(&current)));
+    assert!(if_none_match("", Some(&current)));
+
+    // `false` means `412 Precondition Failed`
+    assert!(if_match(r#""v2""#, Some(&current)));
+    assert!(if_match(r#""v1","v2""#, Some(&current)));
+    assert!(if_match("*", Some(&current)));
+    assert!(!if_match(r#"W/"v2""#, Some(&current)));
+    assert!(!if_match(r#""v2""#, Some(&weak)));
+    assert!(!if_match(r#""v1""#, Some(&current)));
+    assert!(!if_match("*", None));
+    assert!(!if_match(r#""v2"#, Some(&current)));
+    assert!(!if_match("", Some(&current)));
+
+    // Tags may contain commas
+    assert!(if_match(r#""a,b", "c""#, Some(&ETag::strong("a,b").unwrap())));
+
+    let doc = JsonApiDocument::from_str(r#"{ "data": { "type": "dogs", "id": "1" } }"#).unwrap();
+    let header = doc.etag().to_string();
+    assert!(!if_none_match(&header, Some(&doc.etag())));
+    // Weak tags never satisfy `If-Match`
+    assert!(!if_match(&header, Some(&doc.etag())));
+    let (_, strong) = canonical_body(&doc).unwrap();
+    assert!(if_match(&strong.to_string(), Some(&strong)));
+}
+
+#[test]
+fn it_rejects_invalid_tags() {
+    let _ = env_logger::try_init();
+    assert_eq!(
+        ETag::strong("has \"quotes\""),
+        Err(InvalidETag("has \"quotes\"".into()))
+    );
+    assert_eq!(ETag::weak("a b"), Err(InvalidETag("a b".into())));
+    assert_eq!(ETag::weak("\u{1}"), Err(InvalidETag("\u{1}".into())));
+    assert_eq!(ETag::strong("").unwrap().to_string(), r#""""#);
+}
@@ -0,0 +1,122 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+extern crate jsonapi;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::extension::*;
+use jsonapi::media_type::MediaType;
+
+mod helper;
+use crate::helper::read_json_file;
+
+const ATOMIC: &str = "https://jsonapi.org/ext/atomic";
+
+fn registry() -> ExtensionRegistry {
+    ExtensionRegistry::new()
+        .register(ATOMIC, "atomic")
+        .register("https://example.com/ext/version", "version")
+}
+
+#[test]
+fn it_reads_extensions_and_profiles() {
+    let _ = env_logger::try_init();
+    let s = read_json_file("data/jsonapi_info_002.json");
+    let doc: serde_json::Value = serde_json::from_str(&s).unwrap();
+    let info: JsonApiInfo = serde_json::from_value(doc["jsonapi"].clone()).unwrap();
+
+    assert_eq!(info.version, Some("1.1".into()));
+    assert!(info.uses_extension(ATOMIC));
+    assert!(!info.uses_extension("https://example.com/ext/version"));
+    assert!(info.uses_profile("http://example.com/profiles/resource-versioning"));
+    assert_eq!(info.profile.as_ref().map(Vec::len), Some(2));
+
+    let info = JsonApiInfo {
+        version: Some("1.0".into()),
+        ..Default::default()
+    };
+    assert_eq!(
+        serde_json::to_string(&info).unwrap(),
+        r#"{"version":"1.0","meta":null}"#
+    );
+}
+
+#[test]
+fn it_validates_namespaced_members() {
+    let _ = env_logger::try_init();
+    let s = read_json_file("data/jsonapi_info_002.json");
+    let doc: serde_json::Value = serde_json::from_str(&s).unwrap();
+    let registry = registry();
+
+    // Attribute values and meta are not checked
+    assert!(registry.validate_document(&doc).is_none());
+
+    assert_eq!(
+        registry.validate(&doc, &[]),
+        Some(vec![
+            ExtensionValidationError::UndeclaredExtension("/atomic:operations".into()),
+            ExtensionValidationError::UndeclaredExtension("/atomic:operations/0/data/attributes/atomic:note".into()),
+        ])
+    );
+
+    let doc: serde_json::Value = serde_json::from_str(
+        r#"{
+            "data": { "type": "articles", "id": "1", "version:id": "42", "a/b:c": 1 }
+        }"#,
+    ).unwrap();
+    assert_eq!(
+        registry.validate(&doc, &["https://example.com/ext/version".to_string(), "https://example.com/ext/other".to_string()]),
+        Some(vec![
+            ExtensionValidationError::UnsupportedExtension("https://example.com/ext/other".into()),
+            ExtensionValidationError::UnknownNamespace("/data/a~1b:c".into()),
+        ])
+    );
+}
+
+#[test]
+fn it_looks_up_extensions() {
+    let _ = env_logger::try_init();
+    let registry = registry();
+    assert_eq!(registry.namespace(ATOMIC), Some("atomic"));
+    assert_eq!(registry.uri("atomic"), Some(ATOMIC));
+    assert_eq!(registry.uri("other"), None);
+
+    let negotiator = registry.negotiator();
+    assert!(negotiator
+        .check_content_type(&format!(r#"application/vnd.api+json; ext="{}""#, ATOMIC))
+        .is_ok());
+    assert!(negotiator
+        .check_content_type(r#"application/vnd.api+json; ext="https://example.com/ext/other""#)
+        .is_err());
+}
+
+#[test]
+fn it_advertises_applied_extensions() {
+    let _ = env_logger::try_init();
+    let registry = registry();
+    let negotiated = MediaType {
+        profile: vec!["https://example.com/profile".into()],
+        ..MediaType::jsonapi()
+    };
+
+    let doc: serde_json::Value = serde_json::from_str(
+        r#"{
+            "data": { "type": "articles", "id": "1", "version:id": "42", "meta": { "atomic:note": 1 } },
+            "other:member": true
+        }"#,
+    ).unwrap();
+    assert_eq!(registry.applied_extensions(&doc), vec!["https://example.com/ext/version"]);
+    assert_eq!(
+        registry.response_media_type(&negotiated, &doc).to_string(),
+        r#"application/vnd.api+json; ext="https://example.com/ext/version"; profile="https://example.com/profile""#
+    );
+
+    let s = read_json_file("data/jsonapi_info_002.json");
+    let doc: serde_json::Value = serde_json::from_str(&s).unwrap();
+    assert_eq!(registry.applied_extensions(&doc), vec![ATOMIC]);
+    let negotiated = MediaType {
+        ext: vec![ATOMIC.into()],
+        ..MediaType::jsonapi()
+    };
+    assert_eq!(registry.response_media_type(&negotiated, &doc), negotiated);
+}
@@ -0,0 +1,81 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+extern crate jsonapi;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use std::collections::HashMap;
+
+fn sample() -> Meta {
+    let mut meta = Meta::new();
+    meta.insert("z".into(), 1.into());
+    meta.insert("a".into(), 2.into());
+    meta.insert("m".into(), 3.into());
+    meta
+}
+
+#[test]
+fn it_behaves_like_a_map() {
+    let _ = env_logger::try_init();
+    let mut meta = sample();
+    assert_eq!(meta.len(), 3);
+    assert_eq!(meta["a"], 2);
+    assert_eq!(meta.get("b"), None);
+    assert!(meta.contains_key("z"));
+
+    assert_eq!(meta.insert("a".into(), 4.into()), Some(2.into()));
+    *meta.get_mut("m").unwrap() = 5.into();
+    meta["z"] = 6.into();
+    assert_eq!(meta.remove("a"), Some(4.into()));
+    assert_eq!(meta.remove("a"), None);
+    meta.retain(|_, value| value != 6);
+    assert_eq!(meta.keys().collect::<Vec<_>>(), vec!["m"]);
+
+    // Equality doesn't depend on order
+    let reversed: Meta = sample().into_iter().rev().collect();
+    assert_eq!(reversed, sample());
+
+    let from_hash_map: Meta = HashMap::from([("a".to_string(), 2.into())]).into();
+    assert_eq!(from_hash_map, Meta::from([("a".to_string(), 2.into())]));
+    assert_eq!(format!("{:?}", from_hash_map), r#"{"a": Number(2)}"#);
+}
+
+#[test]
+fn it_offers_the_hash_map_api() {
+    let _ = env_logger::try_init();
+    let mut meta = sample();
+    *meta.entry("count".into()).or_insert(0.into()) = 1.into();
+    meta.entry("count".into()).and_modify(|count| *count = 2.into()).or_default();
+    assert_eq!(meta.entry("z".into()).key(), "z");
+    assert_eq!(meta["count"], 2);
+
+    meta.extend(vec![("b".to_string(), 7.into())]);
+    assert_eq!(meta.get_key_value("b"), Some((&"b".to_string(), &7.into())));
+    assert_eq!(meta.remove_entry("b"), Some(("b".to_string(), 7.into())));
+
+    let mut names: Vec<String> = meta.clone().into_keys().collect();
+    names.sort();
+    assert_eq!(names, vec!["a", "count", "m", "z"]);
+    assert_eq!(meta.into_values().count(), 4);
+}
+
+#[test]
+fn it_serializes_as_an_object() {
+    let _ = env_logger::try_init();
+    let meta = sample();
+    let serialized = serde_json::to_string(&meta).unwrap();
+    assert_eq!(serde_json::from_str::<Meta>(&serialized).unwrap(), meta);
+    assert!(serde_json::from_str::<Meta>("[]").is_err());
+
+    let s = r#"{ "type": "a", "id": "1", "attributes": { "z": 1, "a": { "c": 2, "b": 3 } } }"#;
+    let resource: Resource = serde_json::from_str(s).unwrap();
+    let names: Vec<&String> = resource.attributes.keys().collect();
+    if cfg!(feature = "preserve_order") {
+        assert_eq!(serialized, r#"{"z":1,"a":2,"m":3}"#);
+        assert_eq!(names, vec!["z", "a"]);
+    } else {
+        // Members are sorted by name, so output is the same on every run
+        assert_eq!(serialized, r#"{"a":2,"m":3,"z":1}"#);
+        assert_eq!(names, vec!["a", "z"]);
+    }
+}
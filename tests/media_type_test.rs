@@ -0,0 +1,140 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+extern crate jsonapi;
+extern crate env_logger;
+
+use jsonapi::api_error::*;
+use jsonapi::api::DocumentError;
+use jsonapi::media_type::*;
+use std::str::FromStr;
+
+const ATOMIC: &str = "https://jsonapi.org/ext/atomic";
+const TIMESTAMPS: &str = "http://example.com/profiles/timestamps";
+
+fn negotiator() -> Negotiator {
+    Negotiator::new()
+        .support_extension(ATOMIC)
+        .support_profile(TIMESTAMPS)
+}
+
+#[test]
+fn it_parses_media_types() {
+    let _ = env_logger::try_init();
+    let media_type = MediaType::from_str(
+        r#"Application/VND.API+JSON;ext="https://jsonapi.org/ext/atomic https://example.com/ext/b" ; profile=http://example.com/p; charset="utf-8""#,
+    ).unwrap();
+    assert!(media_type.is_jsonapi());
+    assert_eq!(media_type.ext, vec![ATOMIC, "https://example.com/ext/b"]);
+    assert_eq!(media_type.profile, vec!["http://example.com/p"]);
+    assert_eq!(media_type.parameter("charset"), Some("utf-8"));
+    assert_eq!(
+        media_type.to_string(),
+        r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic https://example.com/ext/b"; profile="http://example.com/p"; charset=utf-8"#
+    );
+    assert_eq!(MediaType::from_str(&media_type.to_string()), Ok(media_type));
+
+    assert_eq!(MediaType::from_str(" "), Err(MediaTypeError::Empty));
+    assert_eq!(
+        MediaType::from_str("json"),
+        Err(MediaTypeError::InvalidType("json".into()))
+    );
+    assert_eq!(
+        MediaType::from_str("application/json; charset"),
+        Err(MediaTypeError::InvalidParameter("charset".into()))
+    );
+
+    let list = MediaType::parse_list(r#"text/html, application/vnd.api+json; ext="a,b", */*;q=0.1"#).unwrap();
+    assert_eq!(list.len(), 3);
+    assert_eq!(list[1].ext, vec!["a,b"]);
+    assert!(list[2].matches_jsonapi());
+}
+
+#[test]
+fn it_checks_content_types() {
+    let _ = env_logger::try_init();
+    let negotiator = negotiator();
+
+    assert_eq!(
+        negotiator.check_content_type(&format!(r#"application/vnd.api+json; ext="{}""#, ATOMIC)),
+        Ok(MediaType {
+            ext: vec![ATOMIC.to_string()],
+            ..MediaType::jsonapi()
+        })
+    );
+    // Unknown profiles are fine
+    assert!(negotiator
+        .check_content_type(r#"application/vnd.api+json; profile="http://example.com/other""#)
+        .is_ok());
+
+    assert_eq!(
+        negotiator.check_content_type("application/json"),
+        Err(NegotiationError::UnsupportedMediaType("application/json".into()))
+    );
+    assert_eq!(
+        negotiator.check_content_type(r#"application/vnd.api+json; ext="http://example.com/ext""#),
+        Err(NegotiationError::UnsupportedExtension("http://example.com/ext".into()))
+    );
+    assert_eq!(
+        negotiator.check_content_type("vnd.api+json"),
+        Err(NegotiationError::InvalidContentType("vnd.api+json".into()))
+    );
+
+    let error = negotiator
+        .check_content_type("application/vnd.api+json; version=1")
+        .unwrap_err();
+    assert_eq!(error.status(), 415);
+    let doc = DocumentError::from_errors(vec![error]);
+    assert_eq!(doc.status(), Some(415));
+    assert_eq!(doc.errors[0].title, Some("Unsupported Media Type".into()));
+    assert_eq!(
+        doc.errors[0].source.as_ref().unwrap().header,
+        Some("Content-Type".into())
+    );
+}
+
+#[test]
+fn it_negotiates_responses() {
+    let _ = env_logger::try_init();
+    let negotiator = negotiator();
+
+    assert_eq!(negotiator.negotiate(None), Ok(MediaType::jsonapi()));
+    assert_eq!(negotiator.negotiate(Some("*/*")), Ok(MediaType::jsonapi()));
+    // A modified instance is ignored in favour of a wildcard
+    assert_eq!(
+        negotiator.negotiate(Some("application/vnd.api+json; charset=utf-8, */*")),
+        Ok(MediaType::jsonapi())
+    );
+
+    // The first usable instance wins, unsupported profiles are dropped
+    let accept = format!(
+        r#"application/vnd.api+json; ext="http://example.com/ext", application/vnd.api+json; ext="{}"; profile="{} http://example.com/other""#,
+        ATOMIC, TIMESTAMPS
+    );
+    let response = negotiator.negotiate(Some(&accept)).unwrap();
+    assert_eq!(response.ext, vec![ATOMIC]);
+    assert_eq!(response.profile, vec![TIMESTAMPS]);
+
+    assert_eq!(
+        negotiator.negotiate(Some("application/vnd.api+json; charset=utf-8")),
+        Err(NegotiationError::NotAcceptable)
+    );
+    assert_eq!(
+        negotiator.negotiate(Some(r#"application/vnd.api+json; ext="http://example.com/ext""#)),
+        Err(NegotiationError::NotAcceptable)
+    );
+    // q=0 means not acceptable at all
+    assert_eq!(
+        negotiator.negotiate(Some("application/vnd.api+json; q=0, application/vnd.api+json; charset=x")),
+        Err(NegotiationError::NotAcceptable)
+    );
+    assert_eq!(
+        negotiator.negotiate(Some("application/vnd.api+json; q=0.5")),
+        Ok(MediaType::jsonapi())
+    );
+
+    // Accept headers without any JSON:API media range are left to the application
+    assert_eq!(negotiator.negotiate(Some("text/html;")), Ok(MediaType::jsonapi()));
+
+    let error = NegotiationError::NotAcceptable.into_jsonapi_error();
+    assert_eq!(error.status, Some("406".into()));
+    assert_eq!(error.source.unwrap().header, Some("Accept".into()));
+}
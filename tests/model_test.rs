@@ -1,3 +1,4 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
 #[macro_use]
 extern crate jsonapi;
 #[macro_use]
@@ -7,7 +8,7 @@ use jsonapi::array::JsonApiArray;
 use jsonapi::model::*;
 
 mod helper;
-use helper::read_json_file;
+use crate::helper::read_json_file;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Author {
@@ -32,7 +33,7 @@ struct Chapter {
     title: String,
     ordering: i32,
 }
-jsonapi_model!(Chapter; "chapters");
+jsonapi_model!(Chapter; "chapters"; try_from);
 
 #[test]
 fn to_jsonapi_document_and_back() {
@@ -109,7 +110,7 @@ fn test_vec_to_jsonapi_document() {
 
 #[test]
 fn from_jsonapi_document() {
-    let json = ::read_json_file("data/author_tolkien.json");
+    let json = crate::read_json_file("data/author_tolkien.json");
 
     // TODO - is this the right thing that we want to test? Shold this cast into a JsonApiDocument
     // and detect if this was a data or an error?
@@ -130,3 +131,227 @@ fn from_jsonapi_document() {
         }
     }
 }
+
+#[test]
+fn to_jsonapi_relationship_document() {
+    let book = Book {
+        id: "1".into(),
+        title: "The Fellowship of the Ring".into(),
+        first_chapter: Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        chapters: vec![
+            Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+            Chapter { id: "2".into(), title: "The Shadow of the Past".into(), ordering: 2 },
+        ],
+    };
+
+    let chapters = book.to_jsonapi_relationship_document("chapters")
+        .expect("Book should have a chapters relationship");
+    assert!(chapters.is_valid());
+    assert_eq!(
+        serde_json::to_string(&chapters).unwrap(),
+        r#"{"data":[{"type":"chapters","id":"1"},{"type":"chapters","id":"2"}]}"#
+    );
+
+    let first_chapter = book.to_jsonapi_relationship_document("first_chapter")
+        .expect("Book should have a first_chapter relationship");
+    assert_eq!(
+        serde_json::to_string(&first_chapter).unwrap(),
+        r#"{"data":{"type":"chapters","id":"1"}}"#
+    );
+
+    assert!(book.to_jsonapi_relationship_document("publisher").is_none());
+}
+
+#[test]
+fn update_from_jsonapi_resource() {
+    let mut book = Book {
+        id: "1".into(),
+        title: "The Fellowship of the Ring".into(),
+        first_chapter: Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        chapters: vec![
+            Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        ],
+    };
+
+    let patch: Resource = serde_json::from_str(r#"{
+        "type": "books",
+        "id": "1",
+        "attributes": { "title": "The Two Towers" },
+        "relationships": {
+            "chapters": {
+                "data": [
+                    { "type": "chapters", "id": "1" },
+                    { "type": "chapters", "id": "2" }
+                ]
+            },
+            "first_chapter": {
+                "links": { "self": "/books/1/relationships/first_chapter" }
+            }
+        }
+    }"#).unwrap();
+    let included: Resources = serde_json::from_str(r#"[
+        { "type": "chapters", "id": "1", "attributes": { "title": "The Departure of Boromir", "ordering": 1 } },
+        { "type": "chapters", "id": "2", "attributes": { "title": "The Riders of Rohan", "ordering": 2 } }
+    ]"#).unwrap();
+
+    book.update_from_jsonapi_resource(&patch, &Some(included))
+        .expect("Book should accept the patch");
+
+    assert_eq!(book.title, "The Two Towers");
+    assert_eq!(book.first_chapter.title, "A Long-expected Party");
+    assert_eq!(book.chapters.len(), 2);
+    assert_eq!(book.chapters[1].title, "The Riders of Rohan");
+
+    // Linkage that can't be resolved leaves the model untouched
+    let unresolved: Resource = serde_json::from_str(r#"{
+        "type": "books",
+        "id": "1",
+        "attributes": { "title": "The Return of the King" },
+        "relationships": { "chapters": { "data": [ { "type": "chapters", "id": "3" } ] } }
+    }"#).unwrap();
+    assert!(book.update_from_jsonapi_resource(&unresolved, &None).is_err());
+    assert_eq!(book.title, "The Two Towers");
+    assert_eq!(book.chapters.len(), 2);
+
+    let other_book: Resource = serde_json::from_str(
+        r#"{ "type": "books", "id": "2", "attributes": { "title": "The Return of the King" } }"#
+    ).unwrap();
+    assert!(book.update_from_jsonapi_resource(&other_book, &None).is_err());
+    assert_eq!(book.title, "The Two Towers");
+
+    let not_a_book: Resource = serde_json::from_str(
+        r#"{ "type": "chapters", "id": "1", "attributes": {} }"#
+    ).unwrap();
+    assert!(book.update_from_jsonapi_resource(&not_a_book, &None).is_err());
+}
+
+#[test]
+fn update_linkage_without_included() {
+    let mut book = Book {
+        id: "1".into(),
+        title: "The Fellowship of the Ring".into(),
+        first_chapter: Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        chapters: vec![
+            Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+            Chapter { id: "2".into(), title: "The Shadow of the Past".into(), ordering: 2 },
+        ],
+    };
+
+    // A request document as sent by a client, with linkage but no `included`
+    let patch: Resource = serde_json::from_str(r#"{
+        "type": "books",
+        "id": "1",
+        "relationships": {
+            "first_chapter": { "data": { "type": "chapters", "id": "2" } },
+            "chapters": { "data": [ { "type": "chapters", "id": "2" }, { "type": "chapters", "id": "1" } ] }
+        }
+    }"#).unwrap();
+    book.update_from_jsonapi_resource(&patch, &None)
+        .expect("Linkage should be resolved against the related models");
+    assert_eq!(book.first_chapter.title, "The Shadow of the Past");
+    assert_eq!(book.chapters.len(), 2);
+    assert_eq!(book.chapters[0].id, "2");
+    assert_eq!(book.chapters[1].title, "A Long-expected Party");
+
+    // Linkage to models that are neither related nor included is an error
+    let patch: Resource = serde_json::from_str(r#"{
+        "type": "books",
+        "id": "1",
+        "relationships": { "first_chapter": { "data": { "type": "chapters", "id": "3" } } }
+    }"#).unwrap();
+    assert!(book.update_from_jsonapi_resource(&patch, &None).is_err());
+    assert_eq!(book.first_chapter.id, "2");
+}
+
+#[test]
+fn from_jsonapi_resource_with_resolvers() {
+    let resource: Resource = serde_json::from_str(r#"{
+        "type": "books",
+        "id": "1",
+        "attributes": { "title": "The Fellowship of the Ring" },
+        "relationships": {
+            "first_chapter": { "data": { "type": "chapters", "id": "1" } },
+            "chapters": { "data": [ { "type": "chapters", "id": "1" }, { "type": "chapters", "id": "2" } ] }
+        }
+    }"#).unwrap();
+    let chapters: Resources = [
+        Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        Chapter { id: "2".into(), title: "The Shadow of the Past".into(), ordering: 2 },
+    ].iter().map(|c| c.to_jsonapi_resource().0).collect();
+
+    // From a slice of resources
+    let book = Book::from_jsonapi_resource_with(&resource, chapters.as_slice())
+        .expect("Book should be resolved from a slice");
+    assert_eq!(book.chapters.len(), 2);
+    assert_eq!(book.first_chapter.title, "A Long-expected Party");
+
+    // From an index
+    let index = IncludedIndex::new(&chapters);
+    assert_eq!(index.len(), 2);
+    let indexed: Book = resource.clone().into_model_with(&index)
+        .expect("Book should be resolved from an index");
+    assert_eq!(indexed, book);
+
+    // From a closure
+    let lookups = std::cell::Cell::new(0);
+    let cache = |identifier: &ResourceIdentifier| {
+        lookups.set(lookups.get() + 1);
+        index.get(identifier).cloned()
+    };
+    let cached: Book = resource.clone().into_model_with(&cache)
+        .expect("Book should be resolved from a closure");
+    assert_eq!(cached, book);
+    assert_eq!(lookups.get(), 3);
+
+    // Slices and indexes lend their resources instead of cloning them
+    let identifier = ResourceIdentifier { _type: "chapters".into(), id: "2".into() };
+    assert!(matches!(chapters.as_slice().resolve(&identifier), Some(std::borrow::Cow::Borrowed(r)) if std::ptr::eq(r, &chapters[1])));
+    assert!(matches!(index.resolve(&identifier), Some(std::borrow::Cow::Borrowed(r)) if std::ptr::eq(r, index.get(&identifier).unwrap())));
+    assert!(matches!(cache.resolve(&identifier), Some(std::borrow::Cow::Owned(_))));
+
+    // Resources that can't be resolved are an error
+    let nothing = |_: &ResourceIdentifier| None;
+    assert!(resource.into_model_with::<Book, _>(&nothing).is_err());
+}
+
+#[test]
+fn into_model_and_try_from() {
+    use std::convert::TryFrom;
+
+    let resource = Chapter { id: "45".into(), title: "The Passing of the Grey Company".into(), ordering: 2 }
+        .to_jsonapi_resource().0;
+
+    let chapter: Chapter = resource.clone().into_model().expect("Chapter should be converted");
+    assert_eq!(chapter.ordering, 2);
+
+    let chapter_again = Chapter::try_from(resource).expect("Chapter should be converted");
+    assert_eq!(chapter, chapter_again);
+
+    let not_a_chapter: Resource = serde_json::from_str(
+        r#"{ "type": "chapters", "id": "1", "attributes": { "title": 1 } }"#
+    ).unwrap();
+    assert!(Chapter::try_from(not_a_chapter).is_err());
+
+    // Models that don't opt in can implement `TryFrom<Resource>` themselves
+    let resource = Book {
+        id: "1".into(),
+        title: "The Fellowship of the Ring".into(),
+        first_chapter: Chapter { id: "1".into(), title: "A Long-expected Party".into(), ordering: 1 },
+        chapters: vec![],
+    }.to_jsonapi_resource().0;
+    let book = Book::try_from(resource).expect("Book should be converted");
+    assert_eq!(book.first_chapter.title, "Chapter 1");
+}
+
+/// Resolves every chapter without an `included` member
+impl std::convert::TryFrom<Resource> for Book {
+    type Error = jsonapi::errors::Error;
+
+    fn try_from(resource: Resource) -> Result<Self, Self::Error> {
+        let chapters = |identifier: &ResourceIdentifier| {
+            let title = format!("Chapter {}", identifier.id);
+            Some(Chapter { id: identifier.id.clone(), title, ordering: 0 }.to_jsonapi_resource().0)
+        };
+        resource.into_model_with(&chapters)
+    }
+}
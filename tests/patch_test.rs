@@ -0,0 +1,319 @@
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::patch::*;
+
+mod helper;
+use crate::helper::read_json_file;
+
+fn post(s: &str) -> Resource {
+    serde_json::from_str(s).unwrap()
+}
+
+#[test]
+fn it_serializes_patchsets() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{"type": "posts", "id": "1", "attributes": {"likes": 250}}"#);
+    let after = post(r#"{"type": "posts", "id": "1", "attributes": {"likes": 251}}"#);
+
+    let patchset = before.diff(after).unwrap();
+    let serialized = serde_json::to_value(&patchset).unwrap();
+    assert_eq!(
+        serialized,
+        json!({
+            "resource_type": "posts",
+            "resource_id": "1",
+            "patches": [{
+                "patch_type": "Attribute",
+                "operation": "Replace",
+                "subject": "likes",
+                "previous": 250,
+                "next": 251
+            }]
+        })
+    );
+
+    let deserialized: PatchSet = serde_json::from_value(serialized).unwrap();
+    assert_eq!(deserialized, patchset);
+}
+
+#[test]
+fn it_converts_patchsets_to_json_patch() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 250 },
+        "relationships": {
+            "tags": { "data": [ { "type": "tags", "id": "1" }, { "type": "tags", "id": "2" } ] }
+        }
+    }"#);
+    let after = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "score": 5 },
+        "relationships": {
+            "tags": { "data": [ { "type": "tags", "id": "2" }, { "type": "tags", "id": "3" } ] }
+        },
+        "meta": { "version": 2 }
+    }"#);
+
+    let patchset = before.diff(after.clone()).unwrap();
+    let json_patch = patchset.to_json_patch(&before).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&json_patch).unwrap(),
+        json!([
+            { "op": "test", "path": "/attributes/likes", "value": 250 },
+            { "op": "remove", "path": "/attributes/likes" },
+            { "op": "add", "path": "/attributes/score", "value": 5 },
+            { "op": "test", "path": "/relationships/tags/data/0", "value": { "type": "tags", "id": "1" } },
+            { "op": "remove", "path": "/relationships/tags/data/0" },
+            { "op": "add", "path": "/relationships/tags/data/1", "value": { "type": "tags", "id": "3" } },
+            { "op": "add", "path": "/meta", "value": {} },
+            { "op": "add", "path": "/meta/version", "value": 2 }
+        ])
+    );
+
+    // Parsing the JSON Patch back describes the same change
+    let parsed = PatchSet::from_json_patch(&before, &json_patch).unwrap();
+    assert_eq!(parsed, patchset);
+    assert_eq!(before.patch(&parsed).unwrap(), after);
+
+    // Members are added at their position in the linkage
+    let inserted = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 250 },
+        "relationships": {
+            "tags": { "data": [ { "type": "tags", "id": "1" }, { "type": "tags", "id": "3" }, { "type": "tags", "id": "2" } ] }
+        }
+    }"#);
+    let json_patch = before.diff(inserted.clone()).unwrap().to_json_patch(&before).unwrap();
+    assert_eq!(json_patch[0].path(), "/relationships/tags/data/1");
+    let parsed = PatchSet::from_json_patch(&before, &json_patch).unwrap();
+    assert_eq!(before.patch(&parsed).unwrap(), inserted);
+}
+
+#[test]
+fn it_removes_members_left_empty() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "likes": 250 },
+        "relationships": { "author": { "data": { "type": "people", "id": "9" } } },
+        "links": { "self": "/posts/1" },
+        "meta": { "version": 1 }
+    }"#);
+    let after = post(r#"{"type": "posts", "id": "1", "attributes": { "likes": 251 }}"#);
+
+    let patchset = before.diff(after.clone()).unwrap();
+    let json_patch = patchset.to_json_patch(&before).unwrap();
+    let removed: Vec<&str> = json_patch
+        .iter()
+        .filter(|operation| matches!(operation, JsonPatchOperation::Remove { .. }))
+        .map(JsonPatchOperation::path)
+        .collect();
+    assert_eq!(
+        removed,
+        vec!["/relationships/author", "/relationships", "/links/self", "/links", "/meta/version", "/meta"]
+    );
+
+    // Applying the JSON Patch has the same result as applying the PatchSet
+    let mut value = serde_json::to_value(&before).unwrap();
+    apply_json_patch(&mut value, &json_patch).unwrap();
+    assert_eq!(value, serde_json::to_value(before.patch(&patchset).unwrap()).unwrap());
+    assert_eq!(value, serde_json::to_value(&after).unwrap());
+
+    // Members that were already empty are left alone
+    let empty = post(r#"{"type": "posts", "id": "1", "attributes": {}, "links": {}}"#);
+    let json_patch = empty.diff(post(r#"{"type": "posts", "id": "1", "attributes": {}, "links": {}}"#))
+        .unwrap()
+        .to_json_patch(&empty)
+        .unwrap();
+    assert!(json_patch.is_empty());
+}
+
+#[test]
+fn it_escapes_json_pointer_tokens() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{"type": "posts", "id": "1", "attributes": {}}"#);
+    let after = post(r#"{"type": "posts", "id": "1", "attributes": {"a/b~c": true}}"#);
+
+    let json_patch = before.diff(after.clone()).unwrap().to_json_patch(&before).unwrap();
+    assert_eq!(json_patch[0].path(), "/attributes/a~1b~0c");
+
+    let parsed = PatchSet::from_json_patch(&before, &json_patch).unwrap();
+    assert_eq!(parsed.patches[0].subject, "a/b~c");
+
+    // Nested subjects are pointers already
+    let nested = post(r#"{"type": "posts", "id": "1", "attributes": {"a/b~c": {"d.e": 1}}}"#);
+    let changed = post(r#"{"type": "posts", "id": "1", "attributes": {"a/b~c": {"d.e": 2}}}"#);
+    let patchset = nested.diff_deep(changed.clone()).unwrap();
+    let json_patch = patchset.to_json_patch(&nested).unwrap();
+    assert_eq!(json_patch[1].path(), "/attributes/a~1b~0c/d.e");
+    let parsed = PatchSet::from_json_patch(&nested, &json_patch).unwrap();
+    assert_eq!(nested.patch(&parsed).unwrap(), changed);
+    assert_eq!(
+        serde_json::to_value(patchset.to_update_document(&nested).unwrap()).unwrap()["data"]["attributes"],
+        json!({ "a/b~c": { "d.e": 2 } })
+    );
+}
+
+#[test]
+fn it_parses_json_patch_documents() {
+    let _ = env_logger::try_init();
+    let s = read_json_file("data/resource_post_001.json");
+    let resource: Resource = serde_json::from_str(&s).unwrap();
+
+    let json_patch: JsonPatch = serde_json::from_str(r#"[
+        { "op": "test", "path": "/attributes/likes", "value": 250 },
+        { "op": "replace", "path": "/attributes/likes", "value": 251 },
+        { "op": "move", "from": "/attributes/heading_a", "path": "/attributes/heading_b" },
+        { "op": "add", "path": "/attributes/tags/1", "value": "omakase" }
+    ]"#).unwrap();
+
+    let patchset = PatchSet::from_json_patch(&resource, &json_patch).unwrap();
+    let summary: Vec<(PatchOperation, &str)> = patchset
+        .patches
+        .iter()
+        .map(|p| (p.operation, p.subject.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (PatchOperation::Replace, "likes"),
+            (PatchOperation::Remove, "heading_a"),
+            (PatchOperation::Add, "heading_b"),
+            (PatchOperation::Replace, "tags"),
+        ]
+    );
+    assert_eq!(patchset.patches[3].next, json!(["rails", "omakase", "news"]));
+
+    let failing_test: JsonPatch = serde_json::from_value(json!([
+        { "op": "test", "path": "/attributes/likes", "value": 1 }
+    ])).unwrap();
+    assert_eq!(
+        PatchSet::from_json_patch(&resource, &failing_test),
+        Err(DiffPatchError::IncorrectPropertyValue("/attributes/likes".into()))
+    );
+
+    let missing: JsonPatch = serde_json::from_value(json!([
+        { "op": "remove", "path": "/attributes/nothing" }
+    ])).unwrap();
+    assert_eq!(
+        PatchSet::from_json_patch(&resource, &missing),
+        Err(DiffPatchError::NonExistentProperty("/attributes/nothing".into()))
+    );
+
+    let change_id: JsonPatch = serde_json::from_value(json!([
+        { "op": "replace", "path": "/id", "value": "2" }
+    ])).unwrap();
+    assert_eq!(
+        PatchSet::from_json_patch(&resource, &change_id),
+        Err(DiffPatchError::IncorrectPropertyValue("id".into()))
+    );
+}
+
+#[test]
+fn it_builds_minimal_update_documents() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 250, "draft": true },
+        "relationships": {
+            "author": { "data": { "type": "people", "id": "9" } },
+            "tags": { "data": [ { "type": "tags", "id": "1" } ] }
+        },
+        "links": { "self": "http://example.com/posts/1" }
+    }"#);
+    let after = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": { "title": "Rails is Omakase", "likes": 251 },
+        "relationships": {
+            "author": { "data": { "type": "people", "id": "9" } },
+            "tags": { "data": [ { "type": "tags", "id": "1" }, { "type": "tags", "id": "2" } ] }
+        },
+        "links": { "self": "http://example.com/posts/one" }
+    }"#);
+
+    let doc = before.diff(after).unwrap().to_update_document(&before).unwrap();
+    assert_eq!(
+        serde_json::to_value(&doc).unwrap(),
+        json!({
+            "data": {
+                "type": "posts",
+                "id": "1",
+                "attributes": { "draft": null, "likes": 251 },
+                "relationships": {
+                    "tags": { "data": [ { "type": "tags", "id": "1" }, { "type": "tags", "id": "2" } ] }
+                }
+            }
+        })
+    );
+    assert!(doc.data.validate("posts", "1").is_none());
+}
+
+#[test]
+fn it_clears_removed_relationships_in_update_documents() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{
+        "type": "posts",
+        "id": "1",
+        "attributes": {},
+        "relationships": {
+            "author": { "data": { "type": "people", "id": "9" } },
+            "tags": { "data": [ { "type": "tags", "id": "1" } ] },
+            "comments": { "links": { "related": "/posts/1/comments" } }
+        }
+    }"#);
+    let after = post(r#"{ "type": "posts", "id": "1", "attributes": {} }"#);
+
+    let doc = before.diff(after).unwrap().to_update_document(&before).unwrap();
+    assert_eq!(
+        serde_json::to_value(&doc).unwrap()["data"]["relationships"],
+        json!({
+            "author": { "data": null },
+            "tags": { "data": [] }
+        })
+    );
+}
+
+#[test]
+fn it_converts_nested_patches_to_json_patch() {
+    let _ = env_logger::try_init();
+    let before = post(r#"{
+        "type": "people", "id": "1",
+        "attributes": { "address": { "city": "Amsterdam" }, "tags": ["a", "b"] }
+    }"#);
+    let after = post(r#"{
+        "type": "people", "id": "1",
+        "attributes": { "address": { "city": "Utrecht" }, "tags": ["a", "b", "c"] }
+    }"#);
+
+    let patchset = before.diff_deep(after.clone()).unwrap();
+    let json_patch = patchset.to_json_patch(&before).unwrap();
+    assert_eq!(
+        serde_json::to_value(&json_patch).unwrap(),
+        json!([
+            { "op": "test", "path": "/attributes/address/city", "value": "Amsterdam" },
+            { "op": "replace", "path": "/attributes/address/city", "value": "Utrecht" },
+            { "op": "add", "path": "/attributes/tags/2", "value": "c" }
+        ])
+    );
+
+    let parsed = PatchSet::from_json_patch(&before, &json_patch).unwrap();
+    assert_eq!(before.patch(&parsed).unwrap(), after);
+
+    let doc = patchset.to_update_document(&before).unwrap();
+    assert_eq!(
+        serde_json::to_value(&doc.data.attributes).unwrap(),
+        json!({ "address": { "city": "Utrecht" }, "tags": ["a", "b", "c"] })
+    );
+}
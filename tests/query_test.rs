@@ -1,3 +1,4 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
 extern crate jsonapi;
 extern crate env_logger;
 
@@ -553,3 +554,23 @@ fn can_generate_page_fields() {
 
     assert_eq!(query_string, "page[size]=5&page[number]=10");
 }
+
+#[test]
+fn can_parse_strictly() {
+    let _ = env_logger::try_init();
+    let query = Query::try_from_params("include=author&page[number]=3&page[size]=10");
+    match query {
+        Ok(query) => {
+            assert_eq!(query.include, Some(vec!["author".to_string()]));
+            assert_eq!(query.page, Some(PageParams { size: 10, number: 3 }));
+        }
+        Err(_) => assert!(false),
+    }
+
+    match Query::try_from_params("page[size]=ten") {
+        Err(jsonapi::errors::Error::Query { parameter, .. }) => {
+            assert_eq!(parameter, Some("page[size]".to_string()));
+        }
+        _ => assert!(false),
+    }
+}
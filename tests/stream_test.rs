@@ -0,0 +1,272 @@
+#![allow(clippy::assertions_on_constants, clippy::bool_assert_comparison)]
+#[macro_use]
+extern crate jsonapi;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate env_logger;
+
+use jsonapi::api::*;
+use jsonapi::array::JsonApiArray;
+use jsonapi::errors::Error;
+use jsonapi::model::*;
+use jsonapi::stream::*;
+use std::io;
+use std::str::FromStr;
+
+mod helper;
+use crate::helper::read_json_file;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+struct Book {
+    id: String,
+    title: String,
+    first_chapter: Chapter,
+    chapters: Vec<Chapter>,
+}
+jsonapi_model!(Book; "books"; has one first_chapter; has many chapters);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+struct Chapter {
+    id: String,
+    title: String,
+    ordering: i32,
+}
+jsonapi_model!(Chapter; "chapters");
+
+fn chapter(id: &str) -> Chapter {
+    Chapter {
+        id: id.into(),
+        title: format!("Chapter {}", id),
+        ordering: id.parse().unwrap(),
+    }
+}
+
+fn books() -> Vec<Book> {
+    (1..=3)
+        .map(|id| Book {
+            id: id.to_string(),
+            title: format!("Book {}", id),
+            first_chapter: chapter("1"),
+            chapters: vec![chapter("1"), chapter(&(id + 1).to_string())],
+        })
+        .collect()
+}
+
+fn parse(output: &[u8]) -> DocumentData {
+    match JsonApiDocument::from_str(std::str::from_utf8(output).unwrap()).unwrap() {
+        JsonApiDocument::Data(doc) => doc,
+        JsonApiDocument::Error(_) => panic!("Expected a data document"),
+    }
+}
+
+#[test]
+fn it_writes_the_same_data_as_vec_to_jsonapi_document() {
+    let _ = env_logger::try_init();
+    let output = write_jsonapi_document(Vec::new(), books()).unwrap();
+    let doc = parse(&output);
+    assert!(JsonApiDocument::Data(doc.clone()).is_valid());
+
+    let expected = match vec_to_jsonapi_document(books()) {
+        JsonApiDocument::Data(doc) => doc,
+        JsonApiDocument::Error(_) => unreachable!(),
+    };
+    assert_eq!(doc.data, expected.data);
+
+    // Every chapter is included once
+    let included = doc.included.as_deref().unwrap();
+    let ids: Vec<&str> = included.iter().map(|resource| resource.id.as_str()).collect();
+    assert_eq!(ids, vec!["1", "2", "3", "4"]);
+
+    let index = IncludedIndex::from_document(&doc);
+    let books: Vec<Book> = doc
+        .primary_resources()
+        .iter()
+        .map(|resource| resource.clone().into_model_with(&index).unwrap())
+        .collect();
+    assert_eq!(books, self::books());
+}
+
+#[test]
+fn it_writes_top_level_members() {
+    let _ = env_logger::try_init();
+    let mut writer = DocumentWriter::new(Vec::new()).exclude_primary();
+    assert_eq!(writer.count(), 0);
+    let chapters = vec![chapter("1"), chapter("2")];
+    for chapter in &chapters {
+        writer.write_model(chapter).unwrap();
+    }
+    // Included resources that are primary data are left out on request
+    writer.include(chapters[0].to_jsonapi_resource().0);
+    writer.include(chapter("5").to_jsonapi_resource().0);
+    assert_eq!(writer.count(), 2);
+
+    let mut meta = Meta::new();
+    meta.insert("count".into(), writer.count().into());
+    let mut links = Links::new();
+    links.insert("self".into(), "https://example.com/chapters".into());
+    let output = writer
+        .meta(meta)
+        .links(links)
+        .jsonapi(JsonApiInfo {
+            version: Some("1.1".into()),
+            ..Default::default()
+        })
+        .finish()
+        .unwrap();
+
+    let doc = parse(&output);
+    assert_eq!(doc.primary_resources().len(), 2);
+    assert_eq!(doc.included.map(|included| included.len()), Some(1));
+    assert_eq!(doc.meta.unwrap()["count"], 2);
+    assert_eq!(doc.links.unwrap()["self"], "https://example.com/chapters");
+    assert_eq!(doc.jsonapi.unwrap().version, Some("1.1".into()));
+}
+
+#[test]
+fn it_keeps_primary_data_in_included_by_default() {
+    let _ = env_logger::try_init();
+    let mut writer = DocumentWriter::new(Vec::new());
+    writer.write_model(&chapter("1")).unwrap();
+    writer.include(chapter("1").to_jsonapi_resource().0);
+    writer.include(chapter("1").to_jsonapi_resource().0);
+    let doc = parse(&writer.finish().unwrap());
+    assert_eq!(doc.primary_resources().len(), 1);
+    assert_eq!(doc.included.map(|included| included.len()), Some(1));
+}
+
+#[test]
+fn it_writes_empty_collections() {
+    let _ = env_logger::try_init();
+    let output = write_jsonapi_document(Vec::new(), Vec::<Chapter>::new()).unwrap();
+    assert_eq!(std::str::from_utf8(&output).unwrap(), r#"{"data":[]}"#);
+}
+
+struct FailingWriter;
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn it_reports_write_failures() {
+    let _ = env_logger::try_init();
+    match write_jsonapi_document(FailingWriter, books()) {
+        Err(Error::Io(err)) => assert_eq!(err.to_string(), "disk full"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+/// Yields its input one byte per read
+struct TrickleReader<'a>(&'a [u8]);
+
+impl io::Read for TrickleReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((byte, rest)), Some(first)) => {
+                *first = *byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn it_reads_resources_one_at_a_time() {
+    let _ = env_logger::try_init();
+    for file in &["data/compound_document.json", "data/collection.json"] {
+        let s = read_json_file(file);
+        let expected = parse(s.as_bytes());
+
+        let mut reader = DocumentReader::new(TrickleReader(s.as_bytes()));
+        let resources: Vec<Resource> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(resources, expected.primary_resources());
+
+        let rest = reader.finish().unwrap();
+        assert_eq!(rest.data, None);
+        assert_eq!(rest.included, expected.included);
+        assert_eq!(rest.links, expected.links);
+        assert_eq!(rest.meta, expected.meta);
+    }
+}
+
+#[test]
+fn it_reads_included_in_a_first_pass() {
+    let _ = env_logger::try_init();
+    let output = write_jsonapi_document(Vec::new(), books()).unwrap();
+
+    let index = read_included(&output[..]).unwrap();
+    assert_eq!(index.len(), 4);
+    let read: Vec<Book> = DocumentReader::new(&output[..])
+        .map(|resource| resource.and_then(|resource| resource.into_model_with(&index)))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(read, books());
+
+    // Members before the primary data are available right away
+    let input = r#"{ "included": [{ "type": "people", "id": "9" }], "data": { "type": "articles", "id": "1" } }"#;
+    let mut reader = DocumentReader::new(input.as_bytes());
+    assert_eq!(reader.included(), None);
+    assert_eq!(reader.next_resource().unwrap().map(|resource| resource.id), Some("1".into()));
+    assert_eq!(reader.included().map(Vec::len), Some(1));
+    assert!(reader.next_resource().unwrap().is_none());
+
+    let mut reader = DocumentReader::new(&br#"{ "data": null, "atomic:results": [1, 2.5e3, "]"] }"#[..]);
+    assert!(reader.next_resource().unwrap().is_none());
+    assert!(reader.finish().is_ok());
+    // Skipped primary data is not deserialized, only checked for syntax
+    let input = r#"{ "data": { "type": "articles", "id": 1 }, "meta": { "count": 1 } }"#;
+    let doc = DocumentReader::new(input.as_bytes()).finish().unwrap();
+    assert_eq!(doc.meta.unwrap()["count"], 1);
+    for input in [r#"{ "data": { "type": "articles", } }"#, r#"{ "data": [{ "id": 1 }, { "id": } ] }"#] {
+        match DocumentReader::new(input.as_bytes()).finish() {
+            Err(Error::Parse { pointer, .. }) => assert!(pointer.unwrap().starts_with("/data")),
+            other => panic!("Unexpected result {:?} for {}", other, input),
+        }
+    }
+}
+
+#[test]
+fn it_reports_read_failures() {
+    let _ = env_logger::try_init();
+    let parse_error = |input: &str| {
+        let mut reader = DocumentReader::new(input.as_bytes());
+        match reader.by_ref().find_map(Result::err) {
+            Some(Error::Parse { pointer, detail }) => {
+                // Readers stop at the first error
+                assert!(reader.next().is_none());
+                (pointer, detail)
+            }
+            other => panic!("Unexpected result {:?} for {}", other, input),
+        }
+    };
+
+    assert_eq!(
+        parse_error(r#"{ "data": [{ "type": "a", "id": "1" }, { "type": "a", "id": 2 }] }"#).0,
+        Some("/data/1/id".into())
+    );
+    assert_eq!(
+        parse_error(r#"{ "data": [{ "type": "a", "id": "1", }] }"#).0,
+        Some("/data/0".into())
+    );
+    assert_eq!(parse_error(r#"{ "errors": [] }"#).0, Some("/errors".into()));
+    assert_eq!(parse_error(r#"{ "meta": [] }"#).0, Some("/meta".into()));
+    assert_eq!(
+        parse_error(r#"{ "data": [{ "type": "a", "id": "1" }"#),
+        (None, "Unexpected end of input at byte 37".into())
+    );
+    assert_eq!(
+        parse_error(r#"{ "data": [] } {}"#),
+        (None, "Trailing characters at byte 15".into())
+    );
+    assert_eq!(parse_error("[]"), (None, "Expected '{', found '[' at byte 0".into()));
+}